@@ -1,17 +1,54 @@
-use sam_persistence::{CacheError, RustBreakCache};
+use sam_core::algorithms::{
+    execution_sequence_for_dependencies, warmup_commands, ErrorDependencyResolution,
+};
+use sam_core::engines::{AliasCollection, ErrorsAliasCollection};
+use sam_core::entities::aliases::Alias;
+use sam_core::entities::identifiers::Identifier;
+use sam_core::entities::processes::{output_with_timeout, ShellCommand};
+use sam_persistence::repositories::{AliasesRepository, VarsRepository};
+use sam_persistence::{CacheError, RustBreakCache, VarsCache};
+use sam_tui::UserInterface;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 
 pub struct CacheEngine {
     pub cache_dir: PathBuf,
     pub ttl: Duration,
+    pub ui_interface: UserInterface,
+    pub aliases: AliasesRepository,
+    pub vars: VarsRepository,
+    pub env_variables: HashMap<String, String>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum CacheCommand {
     PrintKeys,
     Clear,
+    ClearVar {
+        var: Identifier,
+    },
+    ClearNamespace {
+        namespace: String,
+    },
+    ShowEntry {
+        key: String,
+    },
+    DeleteEntry,
+    Stats,
+    Warm {
+        alias: Option<Identifier>,
+        namespace: Option<String>,
+        timeout: Duration,
+    },
+}
+
+enum WarmupOutcome {
+    Warmed(usize),
+    AlreadyCached,
+    Failed(String),
+    TimedOut,
 }
 
 impl CacheEngine {
@@ -19,11 +56,21 @@ impl CacheEngine {
         match cmd {
             CacheCommand::PrintKeys => self.print_keys(),
             CacheCommand::Clear => self.cache_clear(),
+            CacheCommand::ClearVar { var } => self.cache_clear_var(&var),
+            CacheCommand::ClearNamespace { namespace } => self.cache_clear_namespace(&namespace),
+            CacheCommand::ShowEntry { key } => self.show_entry(&key),
+            CacheCommand::DeleteEntry => self.delete_entry(),
+            CacheCommand::Stats => self.stats(),
+            CacheCommand::Warm {
+                alias,
+                namespace,
+                timeout,
+            } => self.warm(alias, namespace, timeout),
         }
     }
 
     fn print_keys(self) -> Result<i32> {
-        let cache = RustBreakCache::with_ttl(self.cache_dir, &self.ttl)?;
+        let cache = self.open()?;
         println!(
             "{}{}Keys present in cache{}\n",
             termion::style::Bold,
@@ -43,9 +90,223 @@ impl CacheEngine {
     }
 
     fn cache_clear(self) -> Result<i32> {
-        Ok(RustBreakCache::with_ttl(self.cache_dir, &self.ttl)?
-            .clear_cache()
-            .map(|_| 0)?)
+        Ok(self.open()?.clear_cache().map(|_| 0)?)
+    }
+
+    fn cache_clear_var(self, var: &Identifier) -> Result<i32> {
+        let deleted = self.open()?.clear_var(var)?;
+        println!("cleared {} cache entries for var {}", deleted, var);
+        Ok(0)
+    }
+
+    fn cache_clear_namespace(self, namespace: &str) -> Result<i32> {
+        let deleted = self.open()?.clear_namespace(namespace)?;
+        println!(
+            "cleared {} cache entries for namespace {}",
+            deleted, namespace
+        );
+        Ok(0)
+    }
+
+    fn show_entry(self, key: &str) -> Result<i32> {
+        let record = self
+            .open()?
+            .record(key)?
+            .ok_or_else(|| ErrorCacheEngine::MissingEntry(key.to_string()))?;
+        let vars: Vec<String> = record.entry.vars.iter().map(ToString::to_string).collect();
+        let var = match vars.is_empty() {
+            true => String::from("unknown"),
+            false => vars.join(", "),
+        };
+        println!(
+            "{}Var:{}\t\t{}",
+            termion::style::Bold,
+            termion::style::Reset,
+            var
+        );
+        println!(
+            "{}Command:{}\t{}",
+            termion::style::Bold,
+            termion::style::Reset,
+            record.entry.command
+        );
+        println!(
+            "{}Cached:{}\t{} ago{}",
+            termion::style::Bold,
+            termion::style::Reset,
+            format_age(record.cached_at),
+            if record.expired { " (expired)" } else { "" },
+        );
+        println!(
+            "{}Output:{}\n{}",
+            termion::style::Bold,
+            termion::style::Reset,
+            record.entry.output
+        );
+        Ok(0)
+    }
+
+    fn delete_entry(self) -> Result<i32> {
+        let cache = self.open()?;
+        cache
+            .interactor(self.ui_interface)
+            .delete_entry()
+            .map_err(|err| ErrorCacheEngine::InteractiveDelete(err.to_string()))?;
+        Ok(0)
+    }
+
+    fn stats(self) -> Result<i32> {
+        let stats = self.open()?.stats()?;
+        println!(
+            "{}{}Cache statistics{}\n",
+            termion::style::Bold,
+            termion::color::Fg(termion::color::Green),
+            termion::style::Reset,
+        );
+        println!("- entries:\t{}", stats.entries);
+        println!("- expired:\t{}", stats.expired);
+        println!("- size:\t\t{} bytes", stats.size);
+        if let Some(oldest) = stats.oldest {
+            println!("- oldest:\t{} ago", format_age(oldest));
+        }
+        Ok(0)
+    }
+
+    fn warm(
+        self,
+        alias: Option<Identifier>,
+        namespace: Option<String>,
+        timeout: Duration,
+    ) -> Result<i32> {
+        let cache = self.open()?;
+        let aliases: Vec<Alias> = match (alias, namespace) {
+            (Some(id), _) => vec![self.aliases.get(&id)?.clone()],
+            (None, Some(ns)) => self
+                .aliases
+                .aliases()
+                .into_iter()
+                .filter(|a| a.namespace() == Some(ns.as_str()))
+                .collect(),
+            (None, None) => self.aliases.aliases(),
+        };
+
+        let mut seen = HashSet::new();
+        let mut commands = vec![];
+        for alias in &aliases {
+            let exec_seq = execution_sequence_for_dependencies(&self.vars, alias)?;
+            for (var, command) in warmup_commands(&self.vars, &self.vars, &exec_seq) {
+                if seen.insert(command.clone()) {
+                    commands.push((var, command));
+                }
+            }
+        }
+
+        let total = commands.len();
+        let (mut warmed, mut cached, mut failed) = (0, 0, 0);
+        for (idx, (var, command)) in commands.into_iter().enumerate() {
+            let status = match self.warm_command(&cache, &var, command.as_str(), timeout) {
+                WarmupOutcome::Warmed(n) => {
+                    warmed += 1;
+                    format!("{}ok{} ({} choices)", green(), reset(), n)
+                }
+                WarmupOutcome::AlreadyCached => {
+                    cached += 1;
+                    String::from("already cached")
+                }
+                WarmupOutcome::Failed(reason) => {
+                    failed += 1;
+                    format!("{}failed{} {}", red(), reset(), reason)
+                }
+                WarmupOutcome::TimedOut => {
+                    failed += 1;
+                    format!("{}timed out{}", red(), reset())
+                }
+            };
+            eprintln!(
+                "[{}/{}] {}{}{} '{}' {}",
+                idx + 1,
+                total,
+                termion::style::Bold,
+                var,
+                termion::style::Reset,
+                command,
+                status,
+            );
+        }
+        println!(
+            "warmed {} commands, {} were already cached, {} failed",
+            warmed, cached, failed
+        );
+        Ok(if failed > 0 { 1 } else { 0 })
+    }
+
+    fn warm_command(
+        &self,
+        cache: &RustBreakCache,
+        var: &Identifier,
+        command: &str,
+        timeout: Duration,
+    ) -> WarmupOutcome {
+        let sh_cmd = ShellCommand::new(command.to_string());
+        let cmd_key = match sh_cmd.replace_env_vars_in_command(&self.env_variables) {
+            Ok(key) => key,
+            Err(err) => return WarmupOutcome::Failed(err.to_string()),
+        };
+        if let Ok(Some(_)) = cache.get(cmd_key.value()) {
+            return WarmupOutcome::AlreadyCached;
+        }
+        let mut to_run = ShellCommand::make_command(sh_cmd);
+        to_run.envs(&self.env_variables);
+        match output_with_timeout(to_run, timeout) {
+            Err(err) if err.kind() == std::io::ErrorKind::TimedOut => WarmupOutcome::TimedOut,
+            Err(err) => WarmupOutcome::Failed(err.to_string()),
+            Ok(output) if output.status.code() == Some(0) && output.stderr.is_empty() => {
+                let stdout = String::from_utf8_lossy(output.stdout.as_slice());
+                match cache.put(var, cmd_key.value(), &stdout) {
+                    Ok(_) => {
+                        WarmupOutcome::Warmed(stdout.lines().filter(|l| !l.is_empty()).count())
+                    }
+                    Err(err) => WarmupOutcome::Failed(err.to_string()),
+                }
+            }
+            Ok(output) => WarmupOutcome::Failed(
+                String::from_utf8_lossy(output.stderr.as_slice())
+                    .lines()
+                    .next()
+                    .unwrap_or("returned a non zero exit code")
+                    .to_string(),
+            ),
+        }
+    }
+
+    fn open(&self) -> Result<RustBreakCache> {
+        Ok(RustBreakCache::with_ttl(&self.cache_dir, &self.ttl)?)
+    }
+}
+
+fn green() -> termion::color::Fg<termion::color::Green> {
+    termion::color::Fg(termion::color::Green)
+}
+
+fn red() -> termion::color::Fg<termion::color::Red> {
+    termion::color::Fg(termion::color::Red)
+}
+
+fn reset() -> termion::style::Reset {
+    termion::style::Reset
+}
+
+fn format_age(timestamp: u64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let secs = now.saturating_sub(timestamp);
+    match secs {
+        s if s < 60 => format!("{}s", s),
+        s if s < 3600 => format!("{}m {}s", s / 60, s % 60),
+        s if s < 86400 => format!("{}h {}m", s / 3600, (s % 3600) / 60),
+        s => format!("{}d {}h", s / 86400, (s % 86400) / 3600),
     }
 }
 
@@ -55,4 +316,18 @@ type Result<T> = std::result::Result<T, ErrorCacheEngine>;
 pub enum ErrorCacheEngine {
     #[error("an error happened while trying to clear the cache\n -> {0}")]
     CacheClear(#[from] CacheError),
+    #[error("no cache entry found for key '{0}'")]
+    MissingEntry(String),
+    #[error("could not delete the selected cache entry\n -> {0}")]
+    InteractiveDelete(String),
+    #[error("could not find the alias to warm up\n -> {0}")]
+    WarmupAlias(#[from] ErrorsAliasCollection),
+    #[error("could not figure out the vars to warm up\n -> {0}")]
+    WarmupDependencies(Box<ErrorDependencyResolution>),
+}
+
+impl From<ErrorDependencyResolution> for ErrorCacheEngine {
+    fn from(err: ErrorDependencyResolution) -> Self {
+        ErrorCacheEngine::WarmupDependencies(Box::new(err))
+    }
 }
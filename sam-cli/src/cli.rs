@@ -1,15 +1,26 @@
 use crate::cache_engine::CacheCommand;
+use crate::completion_engine::{CandidateKind, CompletionCommand};
 use crate::config_engine::ConfigCommand;
+use crate::export_engine::ExportCommand;
+use crate::history_engine::{ExportFormat, HistoryCommand};
+use crate::import_engine::ImportCommand;
 use crate::preview_engine::PreviewCommand;
+use crate::resolve_engine::{ResolveCommand, ResolveFormat};
+use crate::stats_engine::{StatsCommand, StatsFormat};
 use crate::HashMap;
-use clap::{App, Arg, ArgMatches, Values};
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone};
+use clap::{App, AppSettings, Arg, ArgMatches, Values};
 use sam_core::engines::SamCommand;
 use sam_core::entities::choices::Choice;
+use sam_core::entities::history::HistoryFilter;
 use sam_core::entities::identifiers;
 use sam_core::entities::identifiers::Identifier;
+use sam_persistence::ShellKind;
 use std::convert::TryFrom;
 use std::env;
 use std::ffi::OsString;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -19,14 +30,51 @@ const ABOUT: &str = "sam lets you difine custom aliases and search them using fu
 const ABOUT_SUB_RUN: &str = "let's you select and alias then run it";
 const ABOUT_SUB_SHOW_LAST: &str = "shows the last command that was run, shortcut is `sam !`";
 const ABOUT_SUB_SHOW_HISTORY: &str = "displays the last commands that you ran";
+const ABOUT_SUB_HISTORY_PICK: &str =
+    "lets you select a history entry then runs it again, edits it or deletes it";
+const ABOUT_SUB_HISTORY_EXPORT: &str =
+    "exports the history as a shell script, a markdown runbook or json";
+const ABOUT_SUB_HISTORY_SAVE: &str =
+    "saves a history entry as a new alias, by default the last entry is saved";
+const DEFAULT_HISTORY_LIMIT: usize = 10;
 const ABOUT_SUB_RUN_LAST: &str = "runs the last command that was run again. shortcut is `sam %`";
 const ABOUT_SUB_MODIFY_RUN_LAST: &str =
     "runs the last command that was run again. shortcut is `sam $`";
 const ABOUT_SUB_CHECK_CONFIG: &str = "checks your configuration files";
+const ABOUT_SUB_CONFIG: &str = "inspects the configuration merged from all the configuration files";
+const ABOUT_SUB_CONFIG_SHOW: &str = "prints every effective setting";
+const ABOUT_SUB_CONFIG_PROFILES: &str = "lists the profiles, the active one is marked with a `*`";
+const ABOUT_SUB_CONFIG_USE_PROFILE: &str =
+    "sets the profile used when neither --profile nor SAM_PROFILE is given";
 const ABOUT_SUB_CACHE_CLEAR: &str = "clears the cache for vars 'from_command' outputs";
 const ABOUT_SUB_CACHE_KEYS: &str = "lists all the cache keys";
+const ABOUT_SUB_CACHE: &str = "inspects and manages the cache for vars 'from_command' outputs";
+const ABOUT_SUB_CACHE_SHOW: &str = "shows the cached output for the provided cache key";
+const ABOUT_SUB_CACHE_DELETE: &str = "lets you select a cache entry then deletes it";
+const ABOUT_SUB_CACHE_CLEAR_TARGETED: &str =
+    "clears the cache, or only the entries of a var or a namespace";
+const ABOUT_SUB_CACHE_STATS: &str = "displays statistics about the cache";
+const ABOUT_SUB_CACHE_WARM: &str =
+    "pre-populates the cache by running the 'from_command' vars of aliases ahead of time";
+const DEFAULT_WARMUP_TIMEOUT_SECS: u64 = 10;
+const ABOUT_SUB_STATS: &str =
+    "reports which aliases and choices are used the most, based on the history";
+const ABOUT_SUB_IMPORT: &str =
+    "converts definitions written for other tools into sam aliases and vars";
+const ABOUT_SUB_IMPORT_SHELL: &str =
+    "imports the aliases and simple functions of a bash or zsh file into a namespace";
+const ABOUT_SUB_IMPORT_NAVI: &str =
+    "imports the navi cheatsheets of a directory, the first tag of a cheatsheet is its namespace";
+const ABOUT_SUB_EXPORT: &str =
+    "prints the aliases as shell aliases and functions that can be sourced by bash, zsh or fish";
+const ABOUT_SUB_COMPLETIONS: &str =
+    "prints the completion script of bash, zsh or fish, it completes aliases, vars and choices";
+const ABOUT_SUB_WIDGET: &str =
+    "prints a bash, zsh or fish key binding that inserts the command of an alias in the prompt";
 const ABOUT_SUB_ALIAS: &str = "run's a provided alias";
 const ABOUT_SUB_PREVIEW: &str = "Display preview of the provided alias";
+const ABOUT_SUB_RESOLVE: &str =
+    "resolves the vars of an alias and prints the result without executing the command";
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum SubCommand {
@@ -34,7 +82,32 @@ pub enum SubCommand {
     CacheCommand(CacheCommand),
     ConfigCheck(ConfigCommand),
     PreviewCommand(PreviewCommand),
+    HistoryCommand(HistoryCommand),
+    StatsCommand(StatsCommand),
+    ImportCommand(ImportCommand),
+    ExportCommand(ExportCommand),
+    CompletionCommand(CompletionCommand),
+    ResolveCommand(ResolveCommand),
 }
+
+impl SubCommand {
+    /// true for the commands that use the values of the `[env]` section. Computing them
+    /// may run commands, the completion and the preview of aliases should stay fast.
+    pub fn needs_env_variables(&self) -> bool {
+        match self {
+            SubCommand::SamCommand(_)
+            | SubCommand::CacheCommand(_)
+            | SubCommand::HistoryCommand(_)
+            | SubCommand::ResolveCommand(_) => true,
+            SubCommand::ConfigCheck(c) => !matches!(
+                c,
+                ConfigCommand::Profiles | ConfigCommand::UseProfile { .. }
+            ),
+            _ => false,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct CLIRequest {
     pub command: SubCommand,
@@ -44,8 +117,12 @@ pub struct CLIRequest {
 #[derive(Clone, Debug, PartialEq)]
 pub struct CLISettings {
     pub dry: bool,
+    pub print: bool,
+    pub non_interactive: bool,
     pub silent: bool,
     pub no_cache: bool,
+    pub config: Option<PathBuf>,
+    pub profile: Option<String>,
     pub default_choices: DefaultChoices,
 }
 
@@ -53,8 +130,12 @@ impl TryFrom<ArgMatches<'_>> for CLISettings {
     type Error = CLIError;
     fn try_from(matches: ArgMatches) -> Result<Self, Self::Error> {
         let dry = matches.is_present("dry");
+        let print = matches.is_present("print");
+        let non_interactive = matches.is_present("non-interactive");
         let silent = matches.is_present("silent");
         let no_cache = matches.is_present("no-cache");
+        let config = matches.value_of("config").map(PathBuf::from);
+        let profile = matches.value_of("profile").map(String::from);
 
         let defaults_extractor = |subcommand: &str| {
             matches
@@ -66,20 +147,41 @@ impl TryFrom<ArgMatches<'_>> for CLISettings {
             .values_of("choices")
             .or_else(|| defaults_extractor("alias"))
             .or_else(|| defaults_extractor("preview"))
+            .or_else(|| defaults_extractor("resolve"))
             .or_else(|| defaults_extractor("run"));
 
         let default_choices = DefaultChoices::try_from(defaults_values)?;
 
         Ok(CLISettings {
             dry,
+            print,
+            non_interactive,
             silent,
             no_cache,
+            config,
+            profile,
             default_choices,
         })
     }
 }
 
-fn app_init() -> App<'static, 'static> {
+pub(crate) fn app_init() -> App<'static, 'static> {
+    // called by the completion scripts.
+    let subc_complete = App::new("__complete")
+        .setting(AppSettings::Hidden)
+        .arg(
+            Arg::with_name("kind")
+                .possible_values(&["aliases", "vars", "choices"])
+                .required(true)
+                .index(1),
+        )
+        .arg(Arg::with_name("prefix").allow_hyphen_values(true).index(2));
+
+    completion_app().subcommand(subc_complete)
+}
+
+/// the command line without its hidden subcommands, the completion scripts are generated from it.
+pub(crate) fn completion_app() -> App<'static, 'static> {
     let arg_choices = Arg::with_name("choices")
         .short("c")
         .long("choices")
@@ -92,6 +194,15 @@ fn app_init() -> App<'static, 'static> {
         .short("d")
         .help("dry run, don't execute the final command.");
 
+    let arg_print = Arg::with_name("print")
+        .long("print")
+        .conflicts_with("dry")
+        .help("prints the final command to stdout instead of executing it.");
+
+    let arg_non_interactive = Arg::with_name("non-interactive")
+        .long("non-interactive")
+        .help("never prompt, fail when a var has no choice. enabled when stdin isn't a terminal.");
+
     let arg_silent = Arg::with_name("silent")
         .long("silent")
         .short("s")
@@ -102,11 +213,101 @@ fn app_init() -> App<'static, 'static> {
         .short("-n")
         .help("avoid relying of the vars cache.");
 
+    let arg_config = Arg::with_name("config")
+        .long("config")
+        .takes_value(true)
+        .help("read the configuration from this file instead of `.sam_rc.toml`.");
+
+    let arg_profile = Arg::with_name("profile")
+        .long("profile")
+        .short("p")
+        .takes_value(true)
+        .help("overlays the settings of a `[profile.<name>]` table of the configuration.");
+
     let subc_run = App::new("run")
         .arg(arg_choices.clone())
         .about(ABOUT_SUB_RUN);
 
-    let subc_display_history = App::new("history").about(ABOUT_SUB_SHOW_HISTORY);
+    let history_filters = [
+        Arg::with_name("alias")
+            .long("alias")
+            .takes_value(true)
+            .help("only keep the executions of this alias. example 'ns::alias' or 'alias'"),
+        Arg::with_name("namespace")
+            .long("namespace")
+            .takes_value(true)
+            .help("only keep the executions of aliases of this namespace."),
+        Arg::with_name("pwd")
+            .long("pwd")
+            .takes_value(true)
+            .help("only keep the executions that happened in this directory. example '--pwd .'"),
+        Arg::with_name("since")
+            .long("since")
+            .takes_value(true)
+            .help("only keep the recent executions. example '2h', '3d', '1w' or '2021-12-24'"),
+        Arg::with_name("failed")
+            .long("failed")
+            .help("only keep the executions that returned a non zero exit code."),
+    ];
+    let subc_display_history = App::new("history")
+        .about(ABOUT_SUB_SHOW_HISTORY)
+        .args(&history_filters)
+        .arg(
+            Arg::with_name("limit")
+                .long("limit")
+                .takes_value(true)
+                .help("the maximum number of entries to display, defaults to 10."),
+        )
+        .arg(
+            Arg::with_name("json")
+                .long("json")
+                .help("prints the entries as json."),
+        )
+        .subcommand(
+            App::new("pick")
+                .about(ABOUT_SUB_HISTORY_PICK)
+                .args(&history_filters),
+        )
+        .subcommand(
+            App::new("export")
+                .about(ABOUT_SUB_HISTORY_EXPORT)
+                .args(&history_filters)
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["sh", "md", "json"])
+                        .default_value("sh")
+                        .help("the format of the export."),
+                ),
+        )
+        .subcommand(
+            App::new("save")
+                .about(ABOUT_SUB_HISTORY_SAVE)
+                .arg(
+                    Arg::with_name("index")
+                        .help("the index of the entry, as displayed by `sam history`.")
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("as")
+                        .long("as")
+                        .takes_value(true)
+                        .required(true)
+                        .help("the identifier of the new alias. example 'ns::name'"),
+                )
+                .arg(
+                    Arg::with_name("desc")
+                        .long("desc")
+                        .takes_value(true)
+                        .help("the description of the new alias, defaults to the original one."),
+                )
+                .arg(
+                    Arg::with_name("keep-defaults")
+                        .long("keep-defaults")
+                        .help("keep the vars of the alias and use the choices as its defaults instead of baking them in."),
+                ),
+        );
     let subc_display_last = App::new("show-last").alias("!").about(ABOUT_SUB_SHOW_LAST);
     let subc_rerun_last = App::new("run-last").alias("%").about(ABOUT_SUB_RUN_LAST);
     let subc_modify_run_last = App::new("modify-run-last")
@@ -131,25 +332,225 @@ fn app_init() -> App<'static, 'static> {
         )
         .arg(arg_choices.clone())
         .about(ABOUT_SUB_PREVIEW);
+    let subc_resolve = App::new("resolve")
+        .arg(
+            Arg::with_name("alias")
+                .help("the alias to resolve.")
+                .required(true)
+                .index(1),
+        )
+        .arg(arg_choices.clone())
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .takes_value(true)
+                .possible_values(&["json", "shell", "env"])
+                .default_value("json")
+                .help("json, the command as a quoted shell string or `KEY=value` lines."),
+        )
+        .about(ABOUT_SUB_RESOLVE);
+
+    let subc_stats = App::new("stats").about(ABOUT_SUB_STATS).arg(
+        Arg::with_name("format")
+            .long("format")
+            .takes_value(true)
+            .possible_values(&["table", "json", "csv"])
+            .default_value("table")
+            .help("the output format of the report."),
+    );
+
+    let arg_import_namespace = Arg::with_name("namespace")
+        .long("namespace")
+        .takes_value(true)
+        .required(true)
+        .help("the namespace the definitions are imported into.");
+    let arg_import_dry_run = Arg::with_name("dry-run")
+        .long("dry-run")
+        .help("shows the changes as a diff instead of writing them.");
+    let subc_import = App::new("import")
+        .about(ABOUT_SUB_IMPORT)
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            App::new("shell")
+                .about(ABOUT_SUB_IMPORT_SHELL)
+                .arg(
+                    Arg::with_name("file")
+                        .help("the shell file to import, ie ~/.bash_aliases")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(arg_import_namespace)
+                .arg(arg_import_dry_run.clone()),
+        )
+        .subcommand(
+            App::new("navi")
+                .about(ABOUT_SUB_IMPORT_NAVI)
+                .arg(
+                    Arg::with_name("dir")
+                        .help("the directory holding the .cheat files")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(arg_import_dry_run),
+        );
+
+    let subc_export = App::new("export")
+        .about(ABOUT_SUB_EXPORT)
+        .arg(
+            Arg::with_name("shell")
+                .long("shell")
+                .takes_value(true)
+                .possible_values(&["bash", "zsh", "fish"])
+                .default_value("bash")
+                .help("the shell the definitions are written for."),
+        )
+        .arg(
+            Arg::with_name("output")
+                .long("output")
+                .short("o")
+                .takes_value(true)
+                .help("writes the definitions to this file instead of stdout."),
+        );
+
+    let subc_completions = App::new("completions").about(ABOUT_SUB_COMPLETIONS).arg(
+        Arg::with_name("shell")
+            .long("shell")
+            .takes_value(true)
+            .possible_values(&["bash", "zsh", "fish"])
+            .default_value("bash")
+            .help("the shell the script is written for."),
+    );
+
+    let subc_widget = App::new("widget").about(ABOUT_SUB_WIDGET).arg(
+        Arg::with_name("shell")
+            .long("shell")
+            .takes_value(true)
+            .possible_values(&["bash", "zsh", "fish"])
+            .default_value("bash")
+            .help("the shell the key binding is written for."),
+    );
+    let subc_config = App::new("config")
+        .about(ABOUT_SUB_CONFIG)
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(App::new("show").about(ABOUT_SUB_CONFIG_SHOW).arg(
+            Arg::with_name("origin").long("origin").help(
+                "also prints the file, or the environment variable, each setting comes from.",
+            ),
+        ))
+        .subcommand(App::new("profiles").about(ABOUT_SUB_CONFIG_PROFILES))
+        .subcommand(
+            App::new("use-profile")
+                .about(ABOUT_SUB_CONFIG_USE_PROFILE)
+                .arg(
+                    Arg::with_name("profile")
+                        .help("the profile to use, without it the base configuration is used")
+                        .index(1),
+                ),
+        );
+
+    let subc_cache = App::new("cache")
+        .about(ABOUT_SUB_CACHE)
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(App::new("keys").about(ABOUT_SUB_CACHE_KEYS))
+        .subcommand(
+            App::new("show").about(ABOUT_SUB_CACHE_SHOW).arg(
+                Arg::with_name("key")
+                    .help("the cache key, as listed by `sam cache keys`.")
+                    .required(true)
+                    .index(1),
+            ),
+        )
+        .subcommand(App::new("delete").about(ABOUT_SUB_CACHE_DELETE))
+        .subcommand(
+            App::new("clear")
+                .about(ABOUT_SUB_CACHE_CLEAR_TARGETED)
+                .arg(
+                    Arg::with_name("var")
+                        .help("only clear the entries produced by this var. example 'ns::var'")
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("namespace")
+                        .long("namespace")
+                        .takes_value(true)
+                        .conflicts_with("var")
+                        .help("only clear the entries produced by vars of this namespace."),
+                ),
+        )
+        .subcommand(App::new("stats").about(ABOUT_SUB_CACHE_STATS))
+        .subcommand(
+            App::new("warm")
+                .about(ABOUT_SUB_CACHE_WARM)
+                .arg(
+                    Arg::with_name("alias")
+                        .help("only warm up the vars of this alias. example 'ns::alias'")
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("namespace")
+                        .long("namespace")
+                        .takes_value(true)
+                        .conflicts_with("alias")
+                        .help("only warm up the vars of the aliases of this namespace."),
+                )
+                .arg(
+                    Arg::with_name("timeout")
+                        .long("timeout")
+                        .takes_value(true)
+                        .help("the maximum time in seconds a single command is allowed to run."),
+                ),
+        );
 
     App::new("sam")
         .version(VERSION)
         .author(AUTHORS)
         .about(ABOUT)
         .arg(arg_dry)
+        .arg(arg_print)
+        .arg(arg_non_interactive)
         .arg(arg_silent)
         .arg(arg_no_cache)
+        .arg(arg_config)
+        .arg(arg_profile)
         .arg(arg_choices.clone())
         .subcommand(subc_run)
         .subcommand(subc_alias)
         .subcommand(subc_preview)
+        .subcommand(subc_resolve)
         .subcommand(subc_display_last)
         .subcommand(subc_rerun_last)
         .subcommand(subc_modify_run_last)
         .subcommand(subc_display_history)
         .subcommand(App::new("check-config").about(ABOUT_SUB_CHECK_CONFIG))
+        .subcommand(subc_config)
         .subcommand(App::new("cache-clear").about(ABOUT_SUB_CACHE_CLEAR))
         .subcommand(App::new("cache-keys").about(ABOUT_SUB_CACHE_KEYS))
+        .subcommand(subc_cache)
+        .subcommand(subc_stats)
+        .subcommand(subc_import)
+        .subcommand(subc_export)
+        .subcommand(subc_completions)
+        .subcommand(subc_widget)
+}
+
+fn parse_shell(shell: Option<&str>) -> ShellKind {
+    match shell {
+        Some("zsh") => ShellKind::Zsh,
+        Some("fish") => ShellKind::Fish,
+        _ => ShellKind::Bash,
+    }
+}
+
+fn parse_config_command(matches: &ArgMatches) -> ConfigCommand {
+    match matches.subcommand() {
+        ("profiles", Some(_)) => ConfigCommand::Profiles,
+        ("use-profile", Some(e)) => ConfigCommand::UseProfile {
+            profile: e.value_of("profile").map(String::from),
+        },
+        (&_, e) => ConfigCommand::Show {
+            origin: e.map(|show| show.is_present("origin")).unwrap_or(false),
+        },
+    }
 }
 
 fn make_cli_request<'a, T, I>(app: App<'a, 'a>, args: I) -> Result<CLIRequest, CLIError>
@@ -175,10 +576,51 @@ where
         ("modify-run-last", Some(_)) => {
             SubCommand::SamCommand(SamCommand::ModifyThenExecuteLastAlias)
         }
-        ("history", Some(_)) => SubCommand::SamCommand(SamCommand::DisplayHistory),
+        ("history", Some(e)) => SubCommand::HistoryCommand(parse_history_command(e)?),
         ("check-config", Some(_)) => SubCommand::ConfigCheck(ConfigCommand::All),
+        ("config", Some(e)) => SubCommand::ConfigCheck(parse_config_command(e)),
         ("cache-clear", Some(_)) => SubCommand::CacheCommand(CacheCommand::Clear),
         ("cache-keys", Some(_)) => SubCommand::CacheCommand(CacheCommand::PrintKeys),
+        ("cache", Some(e)) => SubCommand::CacheCommand(parse_cache_command(e)?),
+        ("stats", Some(e)) => {
+            let format = match e.value_of("format") {
+                Some("json") => StatsFormat::Json,
+                Some("csv") => StatsFormat::Csv,
+                _ => StatsFormat::Table,
+            };
+            SubCommand::StatsCommand(StatsCommand::Report { format })
+        }
+        ("import", Some(e)) => SubCommand::ImportCommand(parse_import_command(e)?),
+        ("export", Some(e)) => SubCommand::ExportCommand(ExportCommand::Shell {
+            shell: parse_shell(e.value_of("shell")),
+            file: e.value_of("output").map(PathBuf::from),
+        }),
+        ("completions", Some(e)) => SubCommand::CompletionCommand(CompletionCommand::Script {
+            shell: parse_shell(e.value_of("shell")),
+        }),
+        ("resolve", Some(e)) => {
+            let alias = parse_alias(e.value_of("alias"))?;
+            let format = match e.value_of("format") {
+                Some("shell") => ResolveFormat::Shell,
+                Some("env") => ResolveFormat::Env,
+                _ => ResolveFormat::Json,
+            };
+            SubCommand::ResolveCommand(ResolveCommand::Resolve { alias, format })
+        }
+        ("widget", Some(e)) => SubCommand::CompletionCommand(CompletionCommand::Widget {
+            shell: parse_shell(e.value_of("shell")),
+        }),
+        ("__complete", Some(e)) => {
+            let kind = match e.value_of("kind") {
+                Some("vars") => CandidateKind::Vars,
+                Some("choices") => CandidateKind::Choices,
+                _ => CandidateKind::Aliases,
+            };
+            SubCommand::CompletionCommand(CompletionCommand::Candidates {
+                kind,
+                prefix: e.value_of("prefix").unwrap_or_default().to_string(),
+            })
+        }
         (&_, _) => SubCommand::SamCommand(SamCommand::ChooseAndExecuteAlias),
     };
     Ok(CLIRequest { command, settings })
@@ -206,6 +648,186 @@ impl TryFrom<Option<Values<'_>>> for DefaultChoices {
     }
 }
 
+fn parse_cache_command(matches: &ArgMatches<'_>) -> Result<CacheCommand, CLIError> {
+    let command = match matches.subcommand() {
+        ("show", Some(e)) => CacheCommand::ShowEntry {
+            key: e.value_of("key").unwrap_or_default().to_string(),
+        },
+        ("delete", Some(_)) => CacheCommand::DeleteEntry,
+        ("clear", Some(e)) => {
+            if let Some(var) = e.value_of("var") {
+                let var = Identifier::from_str(var);
+                if var.namespace.is_none() {
+                    return Err(CLIError::MissingNamespaceForVar(var));
+                }
+                CacheCommand::ClearVar { var }
+            } else if let Some(namespace) = e.value_of("namespace") {
+                CacheCommand::ClearNamespace {
+                    namespace: namespace.to_string(),
+                }
+            } else {
+                CacheCommand::Clear
+            }
+        }
+        ("stats", Some(_)) => CacheCommand::Stats,
+        ("warm", Some(e)) => {
+            let alias = e
+                .value_of("alias")
+                .map(|a| parse_alias(Some(a)))
+                .transpose()?;
+            let timeout = match e.value_of("timeout") {
+                Some(t) => t
+                    .parse()
+                    .map_err(|_| CLIError::MalformedTimeout(t.to_string()))?,
+                None => DEFAULT_WARMUP_TIMEOUT_SECS,
+            };
+            CacheCommand::Warm {
+                alias,
+                namespace: e.value_of("namespace").map(ToString::to_string),
+                timeout: Duration::from_secs(timeout),
+            }
+        }
+        (&_, _) => CacheCommand::PrintKeys,
+    };
+    Ok(command)
+}
+
+fn parse_import_command(matches: &ArgMatches<'_>) -> Result<ImportCommand, CLIError> {
+    let (source, e) = match matches.subcommand() {
+        (source, Some(e)) => (source, e),
+        (source, None) => return Err(CLIError::UnknownImportSource(source.to_string())),
+    };
+    let dry_run = e.is_present("dry-run");
+    match source {
+        "shell" => {
+            let namespace = e.value_of("namespace").unwrap_or_default().to_string();
+            if namespace.is_empty()
+                || namespace.contains(['/', ':'])
+                || namespace.contains(char::is_whitespace)
+            {
+                return Err(CLIError::InvalidNamespace(namespace));
+            }
+            Ok(ImportCommand::Shell {
+                file: PathBuf::from(e.value_of("file").unwrap_or_default()),
+                namespace,
+                dry_run,
+            })
+        }
+        "navi" => Ok(ImportCommand::Navi {
+            dir: PathBuf::from(e.value_of("dir").unwrap_or_default()),
+            dry_run,
+        }),
+        _ => Err(CLIError::UnknownImportSource(source.to_string())),
+    }
+}
+
+fn parse_history_command(matches: &ArgMatches<'_>) -> Result<HistoryCommand, CLIError> {
+    match matches.subcommand() {
+        ("pick", Some(e)) => {
+            return Ok(HistoryCommand::Pick {
+                filter: parse_history_filter(e)?,
+            })
+        }
+        ("export", Some(e)) => {
+            let format = match e.value_of("format") {
+                Some("md") => ExportFormat::Markdown,
+                Some("json") => ExportFormat::Json,
+                _ => ExportFormat::Shell,
+            };
+            return Ok(HistoryCommand::Export {
+                filter: parse_history_filter(e)?,
+                format,
+            });
+        }
+        ("save", Some(e)) => {
+            let index = e
+                .value_of("index")
+                .map(|i| {
+                    i.parse()
+                        .map_err(|_| CLIError::MalformedIndex(i.to_string()))
+                })
+                .transpose()?;
+            let name = parse_alias(e.value_of("as"))?;
+            if name.namespace.is_none() {
+                return Err(CLIError::MissingNamespaceForAlias(name));
+            }
+            return Ok(HistoryCommand::Save {
+                index,
+                name,
+                desc: e.value_of("desc").map(ToString::to_string),
+                keep_defaults: e.is_present("keep-defaults"),
+            });
+        }
+        _ => {}
+    }
+    let limit = match matches.value_of("limit") {
+        Some(l) => l
+            .parse()
+            .map_err(|_| CLIError::MalformedLimit(l.to_string()))?,
+        None => DEFAULT_HISTORY_LIMIT,
+    };
+    Ok(HistoryCommand::Display {
+        filter: parse_history_filter(matches)?,
+        limit,
+        json: matches.is_present("json"),
+    })
+}
+
+fn parse_history_filter(matches: &ArgMatches<'_>) -> Result<HistoryFilter, CLIError> {
+    let pwd = match matches.value_of("pwd") {
+        // the history records canonical paths, a directory that no longer exists can't be
+        // canonicalized but may still be found in the history.
+        Some(p) => {
+            let path = env::current_dir()
+                .map(|cwd| cwd.join(p))
+                .map_err(|err| CLIError::InvalidPwd(p.to_string(), err))?;
+            let path = path.canonicalize().unwrap_or(path);
+            Some(path.to_string_lossy().to_string())
+        }
+        None => None,
+    };
+    Ok(HistoryFilter {
+        alias: matches.value_of("alias").map(Identifier::from_str),
+        namespace: matches.value_of("namespace").map(ToString::to_string),
+        pwd,
+        since: matches.value_of("since").map(parse_since).transpose()?,
+        failed: matches.is_present("failed"),
+    })
+}
+
+// parses either a duration relative to now, like `90m`, `2h`, `3d` or `1w`,
+// or a local date like `2021-12-24` or `2021-12-24 18:30:00`
+// into a unix timestamp.
+fn parse_since(since: &str) -> Result<u64, CLIError> {
+    let malformed = || CLIError::MalformedSince(since.to_string());
+    let units = [
+        ('s', 1),
+        ('m', 60),
+        ('h', 3600),
+        ('d', 86400),
+        ('w', 604800),
+    ];
+    for (suffix, secs) in units {
+        if let Some(amount) = since.strip_suffix(suffix) {
+            if let Ok(amount) = amount.parse::<u64>() {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_err(|_| malformed())?
+                    .as_secs();
+                return Ok(now.saturating_sub(amount * secs));
+            }
+        }
+    }
+    let naive = NaiveDateTime::parse_from_str(since, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDate::parse_from_str(since, "%Y-%m-%d").map(|d| d.and_hms(0, 0, 0)))
+        .map_err(|_| malformed())?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|d| d.timestamp().max(0) as u64)
+        .ok_or_else(malformed)
+}
+
 fn parse_alias(alias: Option<&str>) -> Result<Identifier, CLIError> {
     if let Some(a) = alias {
         Ok(identifiers::Identifier::from_str(a))
@@ -215,13 +837,13 @@ fn parse_alias(alias: Option<&str>) -> Result<Identifier, CLIError> {
 }
 
 fn parse_choice(default: &str) -> Result<(Identifier, Choice), CLIError> {
-    let parts: Vec<&str> = default.split('=').collect();
-    if parts.len() == 2 {
-        let id = Identifier::from_str(parts[0]);
+    // only the first `=` separates the var from its value, the value may contain others.
+    if let Some((var, value)) = default.split_once('=') {
+        let id = Identifier::from_str(var);
         if id.namespace.is_none() {
             Err(CLIError::MissingNamespaceForChoice(id, default.to_string()))
         } else {
-            let choice = Choice::new(parts[1], None);
+            let choice = Choice::new(value, None);
             Ok((id, choice))
         }
     } else {
@@ -237,16 +859,51 @@ pub enum CLIError {
     MissingNamespaceForChoice(Identifier, String),
     #[error("malformed choice {0}, it should be -c namespace::var_name=choice")]
     MalformedChoice(String),
+    #[error("The variable name '{0}' does not have a namespace, it should be namespace::var_name")]
+    MissingNamespaceForVar(Identifier),
+    #[error("malformed timeout {0}, it should be a number of seconds")]
+    MalformedTimeout(String),
+    #[error("malformed index {0}, it should be the index displayed by `sam history`")]
+    MalformedIndex(String),
+    #[error("The alias name '{0}' does not have a namespace, it should be namespace::alias_name")]
+    MissingNamespaceForAlias(Identifier),
+    #[error("malformed limit {0}, it should be a number of entries")]
+    MalformedLimit(String),
+    #[error(
+        "malformed date {0}, it should be a duration like '2h', '3d' or a date like '2021-12-24'"
+    )]
+    MalformedSince(String),
+    #[error("invalid namespace '{0}', it can't be empty or contain '/', ':' or whitespaces")]
+    InvalidNamespace(String),
+    #[error("unknown import source '{0}'")]
+    UnknownImportSource(String),
+    #[error("the directory {0} can't be used to filter the history\n-> {1}")]
+    InvalidPwd(String, std::io::Error),
 }
 
 #[cfg(test)]
 mod tests {
 
-    use crate::{cli::DefaultChoices, preview_engine::PreviewCommand};
+    use crate::{
+        cache_engine::CacheCommand,
+        cli::DefaultChoices,
+        completion_engine::{CandidateKind, CompletionCommand},
+        config_engine::ConfigCommand,
+        export_engine::ExportCommand,
+        history_engine::{ExportFormat, HistoryCommand},
+        import_engine::ImportCommand,
+        preview_engine::PreviewCommand,
+        resolve_engine::{ResolveCommand, ResolveFormat},
+    };
     use maplit::hashmap;
+    use sam_core::entities::history::HistoryFilter;
     use sam_core::entities::{choices::Choice, identifiers::Identifier};
+    use sam_persistence::ShellKind;
+    use sam_utils::fsutils::TempDirectory;
+    use std::path::PathBuf;
+    use std::time::Duration;
 
-    use super::{app_init, make_cli_request, CLIRequest, SubCommand};
+    use super::{app_init, make_cli_request, parse_since, CLIRequest, SubCommand};
     use crate::cli::CLISettings;
     use sam_core::engines::SamCommand;
 
@@ -267,8 +924,12 @@ mod tests {
             }),
             settings: CLISettings {
                 dry: false,
+                print: false,
+                non_interactive: false,
                 silent: false,
                 no_cache: false,
+                config: None,
+                profile: None,
                 default_choices: DefaultChoices(hashmap! {
                 Identifier::with_namespace("some_choice", Some("some_ns")) => Choice::from_value("value"),
                 Identifier::with_namespace("some_other_choice", Some("some_ns")) => Choice::from_value("value2"),
@@ -296,8 +957,12 @@ mod tests {
             }),
             settings: CLISettings {
                 dry: false,
+                print: false,
+                non_interactive: false,
                 silent: false,
                 no_cache: false,
+                config: None,
+                profile: None,
                 default_choices: DefaultChoices(hashmap! {
                 Identifier::with_namespace("some_choice", Some("some_ns")) => Choice::from_value("value"),
                 Identifier::with_namespace("some_other_choice", Some("some_ns")) => Choice::from_value("value2"),
@@ -321,8 +986,12 @@ mod tests {
             command: SubCommand::SamCommand(SamCommand::ChooseAndExecuteAlias {}),
             settings: CLISettings {
                 dry: false,
+                print: false,
+                non_interactive: false,
                 silent: false,
                 no_cache: false,
+                config: None,
+                profile: None,
                 default_choices: DefaultChoices(hashmap! {
                 Identifier::with_namespace("some_choice", Some("some_ns")) => Choice::from_value("value"),
                 Identifier::with_namespace("some_other_choice", Some("some_ns")) => Choice::from_value("value2"),
@@ -346,8 +1015,12 @@ mod tests {
             command: SubCommand::SamCommand(SamCommand::ChooseAndExecuteAlias {}),
             settings: CLISettings {
                 dry: false,
+                print: false,
+                non_interactive: false,
                 silent: false,
                 no_cache: false,
+                config: None,
+                profile: None,
                 default_choices: DefaultChoices(hashmap! {
                 Identifier::with_namespace("some_choice", Some("some_ns")) => Choice::from_value("value"),
                 Identifier::with_namespace("some_other_choice", Some("some_ns")) => Choice::from_value("value2"),
@@ -357,4 +1030,364 @@ mod tests {
 
         assert_eq!(request.unwrap(), expected_cli_request);
     }
+
+    #[test]
+    fn cache_subcommand() {
+        let cases = vec![
+            (vec!["sam", "cache", "keys"], CacheCommand::PrintKeys),
+            (vec!["sam", "cache", "clear"], CacheCommand::Clear),
+            (
+                vec!["sam", "cache", "clear", "some_ns::some_var"],
+                CacheCommand::ClearVar {
+                    var: Identifier::with_namespace("some_var", Some("some_ns")),
+                },
+            ),
+            (
+                vec!["sam", "cache", "clear", "--namespace", "some_ns"],
+                CacheCommand::ClearNamespace {
+                    namespace: String::from("some_ns"),
+                },
+            ),
+            (
+                vec!["sam", "cache", "show", "ls -l"],
+                CacheCommand::ShowEntry {
+                    key: String::from("ls -l"),
+                },
+            ),
+            (vec!["sam", "cache", "delete"], CacheCommand::DeleteEntry),
+            (vec!["sam", "cache", "stats"], CacheCommand::Stats),
+            (
+                vec!["sam", "cache", "warm", "ns::alias", "--timeout", "3"],
+                CacheCommand::Warm {
+                    alias: Some(Identifier::with_namespace("alias", Some("ns"))),
+                    namespace: None,
+                    timeout: Duration::from_secs(3),
+                },
+            ),
+            (
+                vec!["sam", "cache", "warm", "--namespace", "ns"],
+                CacheCommand::Warm {
+                    alias: None,
+                    namespace: Some(String::from("ns")),
+                    timeout: Duration::from_secs(10),
+                },
+            ),
+        ];
+        for (args, expected) in cases {
+            let request = make_cli_request(app_init(), args).expect("should parse");
+            assert_eq!(request.command, SubCommand::CacheCommand(expected));
+        }
+
+        let request = make_cli_request(app_init(), ["sam", "cache", "clear", "some_var"]);
+        assert!(request.is_err());
+    }
+
+    #[test]
+    fn history_subcommand() {
+        let request = make_cli_request(app_init(), ["sam", "history"]).expect("should parse");
+        assert_eq!(
+            request.command,
+            SubCommand::HistoryCommand(HistoryCommand::Display {
+                filter: HistoryFilter::default(),
+                limit: 10,
+                json: false,
+            })
+        );
+
+        let args = [
+            "sam",
+            "history",
+            "--limit",
+            "3",
+            "--alias",
+            "ns::alias",
+            "--failed",
+            "--json",
+        ];
+        let request = make_cli_request(app_init(), args).expect("should parse");
+        assert_eq!(
+            request.command,
+            SubCommand::HistoryCommand(HistoryCommand::Display {
+                filter: HistoryFilter {
+                    alias: Some(Identifier::with_namespace("alias", Some("ns"))),
+                    failed: true,
+                    ..HistoryFilter::default()
+                },
+                limit: 3,
+                json: true,
+            })
+        );
+
+        let args = ["sam", "history", "pick", "--namespace", "ns"];
+        let request = make_cli_request(app_init(), args).expect("should parse");
+        assert_eq!(
+            request.command,
+            SubCommand::HistoryCommand(HistoryCommand::Pick {
+                filter: HistoryFilter {
+                    namespace: Some(String::from("ns")),
+                    ..HistoryFilter::default()
+                },
+            })
+        );
+
+        let args = ["sam", "history", "export", "--format", "md", "--failed"];
+        let request = make_cli_request(app_init(), args).expect("should parse");
+        assert_eq!(
+            request.command,
+            SubCommand::HistoryCommand(HistoryCommand::Export {
+                filter: HistoryFilter {
+                    failed: true,
+                    ..HistoryFilter::default()
+                },
+                format: ExportFormat::Markdown,
+            })
+        );
+
+        let args = [
+            "sam",
+            "history",
+            "save",
+            "3",
+            "--as",
+            "ns::preset",
+            "--keep-defaults",
+        ];
+        let request = make_cli_request(app_init(), args).expect("should parse");
+        assert_eq!(
+            request.command,
+            SubCommand::HistoryCommand(HistoryCommand::Save {
+                index: Some(3),
+                name: Identifier::with_namespace("preset", Some("ns")),
+                desc: None,
+                keep_defaults: true,
+            })
+        );
+        let args = ["sam", "history", "save", "--as", "preset"];
+        assert!(make_cli_request(app_init(), args).is_err());
+    }
+
+    #[test]
+    fn history_pwd() {
+        let tmp = TempDirectory::new().expect("can't create temp dir for test");
+        std::fs::create_dir(tmp.path.join("sub")).unwrap();
+        let pwd_filter = |pwd: &str| {
+            let args = ["sam", "history", "--pwd", pwd];
+            match make_cli_request(app_init(), args)
+                .expect("should parse")
+                .command
+            {
+                SubCommand::HistoryCommand(HistoryCommand::Display { filter, .. }) => filter.pwd,
+                other => panic!("unexpected command {:?}", other),
+            }
+        };
+        let canonical = tmp.path.canonicalize().unwrap();
+        let pwd = pwd_filter(&format!("{}/sub/..", tmp.path.display()));
+        assert_eq!(pwd, Some(canonical.to_string_lossy().to_string()));
+
+        // directories that were removed since are kept as is.
+        let gone = tmp.path.join("gone");
+        let pwd = pwd_filter(&gone.to_string_lossy());
+        assert_eq!(pwd, Some(gone.to_string_lossy().to_string()));
+    }
+
+    #[test]
+    fn history_since() {
+        let two_hours_ago = parse_since("2h").expect("should parse a relative duration");
+        let one_hour_ago = parse_since("1h").expect("should parse a relative duration");
+        assert_eq!(one_hour_ago - two_hours_ago, 3600);
+        assert!(parse_since("2021-12-24").is_ok());
+        assert!(parse_since("2021-12-24 18:30:00").is_ok());
+        assert!(parse_since("yesterday").is_err());
+    }
+
+    #[test]
+    fn import_subcommand() {
+        let args = ["sam", "import", "shell", "aliases.sh", "--namespace", "ns"];
+        let request = make_cli_request(app_init(), args).expect("should parse");
+        assert_eq!(
+            request.command,
+            SubCommand::ImportCommand(ImportCommand::Shell {
+                file: PathBuf::from("aliases.sh"),
+                namespace: String::from("ns"),
+                dry_run: false,
+            })
+        );
+
+        let args = ["sam", "import", "navi", "cheats", "--dry-run"];
+        let request = make_cli_request(app_init(), args).expect("should parse");
+        assert_eq!(
+            request.command,
+            SubCommand::ImportCommand(ImportCommand::Navi {
+                dir: PathBuf::from("cheats"),
+                dry_run: true,
+            })
+        );
+
+        let args = [
+            "sam",
+            "import",
+            "shell",
+            "aliases.sh",
+            "--namespace",
+            "ns::x",
+        ];
+        assert!(make_cli_request(app_init(), args).is_err());
+    }
+
+    #[test]
+    fn export_subcommand() {
+        let args = ["sam", "export", "--shell", "fish", "-o", "sam.fish"];
+        let request = make_cli_request(app_init(), args).expect("should parse");
+        assert_eq!(
+            request.command,
+            SubCommand::ExportCommand(ExportCommand::Shell {
+                shell: ShellKind::Fish,
+                file: Some(PathBuf::from("sam.fish")),
+            })
+        );
+
+        let request = make_cli_request(app_init(), ["sam", "export"]).expect("should parse");
+        assert_eq!(
+            request.command,
+            SubCommand::ExportCommand(ExportCommand::Shell {
+                shell: ShellKind::Bash,
+                file: None,
+            })
+        );
+
+        // exported functions pass their arguments as choices, they may contain a `=`.
+        let args = ["sam", "alias", "ns::alias", "-c", "ns::query=a=b"];
+        let request = make_cli_request(app_init(), args).expect("should parse");
+        assert_eq!(
+            request.settings.default_choices,
+            DefaultChoices(hashmap! {
+                Identifier::with_namespace("query", Some("ns")) => Choice::from_value("a=b"),
+            })
+        );
+    }
+
+    #[test]
+    fn completion_subcommands() {
+        let args = ["sam", "completions", "--shell", "zsh"];
+        let request = make_cli_request(app_init(), args).expect("should parse");
+        assert_eq!(
+            request.command,
+            SubCommand::CompletionCommand(CompletionCommand::Script {
+                shell: ShellKind::Zsh
+            })
+        );
+
+        let args = ["sam", "__complete", "choices", "ns::var="];
+        let request = make_cli_request(app_init(), args).expect("should parse");
+        assert_eq!(
+            request.command,
+            SubCommand::CompletionCommand(CompletionCommand::Candidates {
+                kind: CandidateKind::Choices,
+                prefix: String::from("ns::var="),
+            })
+        );
+    }
+
+    #[test]
+    fn resolve_subcommand() {
+        let args = [
+            "sam",
+            "resolve",
+            "ns::alias",
+            "-c",
+            "ns::var=value",
+            "--format",
+            "env",
+        ];
+        let request = make_cli_request(app_init(), args).expect("should parse");
+        assert_eq!(
+            request.command,
+            SubCommand::ResolveCommand(ResolveCommand::Resolve {
+                alias: Identifier::with_namespace("alias", Some("ns")),
+                format: ResolveFormat::Env,
+            })
+        );
+        assert_eq!(
+            request.settings.default_choices,
+            DefaultChoices(hashmap! {
+                Identifier::with_namespace("var", Some("ns")) => Choice::from_value("value"),
+            })
+        );
+    }
+
+    #[test]
+    fn print_flag() {
+        let args = ["sam", "--print", "alias", "ns::alias"];
+        let request = make_cli_request(app_init(), args).expect("should parse");
+        assert!(request.settings.print);
+        assert!(!request.settings.dry);
+        assert!(!request.settings.non_interactive);
+
+        let args = ["sam", "--non-interactive", "alias", "ns::alias"];
+        let request = make_cli_request(app_init(), args).expect("should parse");
+        assert!(request.settings.non_interactive);
+
+        let args = ["sam", "widget", "--shell", "fish"];
+        let request = make_cli_request(app_init(), args).expect("should parse");
+        assert_eq!(
+            request.command,
+            SubCommand::CompletionCommand(CompletionCommand::Widget {
+                shell: ShellKind::Fish
+            })
+        );
+    }
+
+    #[test]
+    fn config_flag() {
+        let args = ["sam", "--config", "/etc/sam.toml", "history"];
+        let request = make_cli_request(app_init(), args).expect("should parse");
+        assert_eq!(
+            request.settings.config,
+            Some(PathBuf::from("/etc/sam.toml"))
+        );
+
+        let request = make_cli_request(app_init(), ["sam", "history"]).expect("should parse");
+        assert_eq!(request.settings.config, None);
+    }
+
+    #[test]
+    fn config_subcommand() {
+        let args = ["sam", "config", "show", "--origin"];
+        let request = make_cli_request(app_init(), args).expect("should parse");
+        assert_eq!(
+            request.command,
+            SubCommand::ConfigCheck(ConfigCommand::Show { origin: true })
+        );
+
+        let args = [
+            "sam",
+            "--profile",
+            "work",
+            "config",
+            "use-profile",
+            "oncall",
+        ];
+        let request = make_cli_request(app_init(), args).expect("should parse");
+        assert_eq!(request.settings.profile, Some(String::from("work")));
+        assert_eq!(
+            request.command,
+            SubCommand::ConfigCheck(ConfigCommand::UseProfile {
+                profile: Some(String::from("oncall"))
+            })
+        );
+    }
+
+    #[test]
+    fn env_variables_are_only_needed_by_commands_that_run_aliases() {
+        let needs_env = |args: &[&str]| {
+            let request = make_cli_request(app_init(), args).expect("should parse");
+            request.command.needs_env_variables()
+        };
+        assert!(needs_env(&["sam", "alias", "ns::alias"]));
+        assert!(needs_env(&["sam", "run"]));
+        assert!(needs_env(&["sam", "config", "show"]));
+        assert!(!needs_env(&["sam", "__complete", "choices", "ns::var="]));
+        assert!(!needs_env(&["sam", "preview", "ns::alias"]));
+        assert!(!needs_env(&["sam", "config", "profiles"]));
+    }
 }
@@ -0,0 +1,344 @@
+use crate::cli;
+use sam_core::algorithms::VarsCollection;
+use sam_core::engines::AliasCollection;
+use sam_core::entities::choices::Choice;
+use sam_core::entities::identifiers::Identifier;
+use sam_persistence::repositories::{AliasesRepository, VarsRepository};
+use sam_persistence::{CacheError, RustBreakCache, ShellKind};
+use sam_readers::read_choices;
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+use thiserror::Error;
+
+const BIN_NAME: &str = "sam";
+
+// clap's completion function is renamed `_sam_clap`, `_sam` completes identifiers and choices
+// then falls back on it. bash splits words on `:` and `=`, the candidates are trimmed accordingly.
+const BASH_HOOK: &str = r#"
+_sam() {
+    local line="${COMP_LINE:0:${COMP_POINT}}"
+    local cur="${line##* }"
+    local words=(${line% *})
+    local prev="${words[${#words[@]}-1]}"
+    local kind
+    case "${prev}" in
+        alias|preview|warm|--alias) kind="aliases" ;;
+        clear) kind="vars" ;;
+        -c|--choices) kind="choices" ;;
+        *) _sam_clap "$@"; return ;;
+    esac
+    local prefix="${cur%"${cur##*[:=]}"}"
+    local IFS=$'\n'
+    COMPREPLY=($(sam __complete "${kind}" "${cur}" 2>/dev/null))
+    COMPREPLY=("${COMPREPLY[@]#"${prefix}"}")
+}
+"#;
+
+const ZSH_HOOK: &str = r#"
+_sam() {
+    local cur="${words[CURRENT]}" prev="${words[CURRENT-1]}" kind
+    case "${prev}" in
+        alias|preview|warm|--alias) kind="aliases" ;;
+        clear) kind="vars" ;;
+        -c|--choices) kind="choices" ;;
+        *) _sam_clap "$@"; return ;;
+    esac
+    local -a candidates
+    candidates=(${(f)"$(sam __complete ${kind} ${cur} 2>/dev/null)"})
+    compadd -a candidates
+}
+
+"#;
+
+const FISH_HOOK: &str = r#"
+complete -c sam -n "__fish_seen_subcommand_from alias preview warm" -f -a "(sam __complete aliases (commandline -ct) 2>/dev/null)"
+complete -c sam -n "__fish_seen_subcommand_from clear" -f -a "(sam __complete vars (commandline -ct) 2>/dev/null)"
+complete -c sam -n "__fish_seen_subcommand_from history" -l alias -x -a "(sam __complete aliases (commandline -ct) 2>/dev/null)"
+complete -c sam -s c -l choices -x -a "(sam __complete choices (commandline -ct) 2>/dev/null)"
+"#;
+
+// the widgets replace the line being edited by the command of the chosen alias, bound to alt-s.
+const BASH_WIDGET: &str = r#"__sam_widget() {
+    local cmd
+    cmd="$(sam --print run)"
+    if [ -n "${cmd}" ]; then
+        READLINE_LINE="${cmd}"
+        READLINE_POINT=${#cmd}
+    fi
+}
+bind -x '"\es": __sam_widget'
+"#;
+
+const ZSH_WIDGET: &str = r#"__sam_widget() {
+    local cmd
+    cmd="$(sam --print run < /dev/tty)"
+    if [ -n "${cmd}" ]; then
+        BUFFER="${cmd}"
+        CURSOR=${#BUFFER}
+    fi
+    zle reset-prompt
+}
+zle -N __sam_widget
+bindkey '\es' __sam_widget
+"#;
+
+const FISH_WIDGET: &str = r#"function __sam_widget
+    set -l cmd (sam --print run | string collect)
+    if test -n "$cmd"
+        commandline -r -- $cmd
+    end
+    commandline -f repaint
+end
+bind \es __sam_widget
+"#;
+
+pub struct CompletionEngine {
+    pub aliases: AliasesRepository,
+    pub vars: VarsRepository,
+    pub cache_dir: PathBuf,
+    pub ttl: Duration,
+    pub output: Box<dyn Write>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompletionCommand {
+    Script { shell: ShellKind },
+    Widget { shell: ShellKind },
+    Candidates { kind: CandidateKind, prefix: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CandidateKind {
+    Aliases,
+    Vars,
+    // `ns::var=value`, the var is read from the prefix.
+    Choices,
+}
+
+impl CompletionEngine {
+    pub fn run(mut self, cmd: CompletionCommand) -> Result<i32> {
+        match cmd {
+            CompletionCommand::Script { shell } => write_script(shell, &mut self.output)?,
+            CompletionCommand::Widget { shell } => write_widget(shell, &mut self.output)?,
+            CompletionCommand::Candidates { kind, prefix } => {
+                for candidate in self.candidates(kind, &prefix)? {
+                    writeln!(self.output, "{}", candidate)?;
+                }
+            }
+        }
+        Ok(0)
+    }
+
+    fn candidates(&self, kind: CandidateKind, prefix: &str) -> Result<BTreeSet<String>> {
+        let candidates: Vec<String> = match kind {
+            CandidateKind::Aliases => self
+                .aliases
+                .identifiers()
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            CandidateKind::Vars => self
+                .vars
+                .vars_iter()
+                .map(|v| v.name().to_string())
+                .collect(),
+            CandidateKind::Choices => match prefix.split_once('=') {
+                Some((var, _)) => self
+                    .choices(&Identifier::from_str(var))?
+                    .into_iter()
+                    .map(|choice| format!("{}={}", var, choice.value()))
+                    .collect(),
+                // the var comes first, the `=` is added so that the choices are completed next.
+                None => self
+                    .vars
+                    .vars_iter()
+                    .map(|v| format!("{}=", v.name()))
+                    .collect(),
+            },
+        };
+        Ok(candidates
+            .into_iter()
+            .filter(|c| c.starts_with(prefix))
+            .collect())
+    }
+
+    // the static choices of the var or, for `from_command` vars, the choices found in the cache.
+    fn choices(&self, id: &Identifier) -> Result<Vec<Choice>> {
+        let var = match self.vars.vars_iter().find(|v| v.name() == *id) {
+            Some(var) if !self.vars.is_secret(id) => var,
+            _ => return Ok(vec![]),
+        };
+        if !var.is_command() {
+            return Ok(var.choices());
+        }
+        if !self.cache_dir.exists() {
+            return Ok(vec![]);
+        }
+        let cache = RustBreakCache::with_ttl(&self.cache_dir, &self.ttl)?;
+        let choices = cache
+            .records()?
+            .filter(|r| !r.expired && r.entry.vars.contains(id))
+            .flat_map(|r| read_choices(r.entry.output.as_bytes()).unwrap_or_default())
+            .collect();
+        Ok(choices)
+    }
+}
+
+/// the completion script of the shell, generated from the command line definition.
+pub fn write_script(shell: ShellKind, output: &mut dyn Write) -> Result<()> {
+    let clap_shell = match shell {
+        ShellKind::Bash => clap::Shell::Bash,
+        ShellKind::Zsh => clap::Shell::Zsh,
+        ShellKind::Fish => clap::Shell::Fish,
+    };
+    let mut generated = vec![];
+    cli::completion_app().gen_completions_to(BIN_NAME, clap_shell, &mut generated);
+    let generated = String::from_utf8_lossy(&generated);
+    match shell {
+        ShellKind::Bash => {
+            write!(
+                output,
+                "{}",
+                generated.replacen("_sam() {", "_sam_clap() {", 1)
+            )?;
+            write!(output, "{}", BASH_HOOK)?;
+        }
+        // the script ends by calling `_sam`, the hook is defined before that.
+        ShellKind::Zsh => {
+            let generated = generated.replacen("\n_sam() {", "\n_sam_clap() {", 1);
+            let body = generated.trim_end().trim_end_matches("_sam \"$@\"");
+            writeln!(output, "{}{}_sam \"$@\"", body, ZSH_HOOK)?;
+        }
+        ShellKind::Fish => {
+            write!(output, "{}{}", generated, FISH_HOOK)?;
+        }
+    }
+    Ok(())
+}
+
+/// the key binding that inserts the command of an alias in the prompt, instead of running it.
+pub fn write_widget(shell: ShellKind, output: &mut dyn Write) -> Result<()> {
+    let widget = match shell {
+        ShellKind::Bash => BASH_WIDGET,
+        ShellKind::Zsh => ZSH_WIDGET,
+        ShellKind::Fish => FISH_WIDGET,
+    };
+    write!(output, "{}", widget)?;
+    Ok(())
+}
+
+type Result<T> = std::result::Result<T, ErrorCompletionEngine>;
+
+#[derive(Debug, Error)]
+pub enum ErrorCompletionEngine {
+    #[error("could not write the completions\n-> {0}")]
+    Output(#[from] std::io::Error),
+    #[error("could not read the cached choices\n-> {0}")]
+    Cache(#[from] CacheError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_script, write_widget, CandidateKind, CompletionEngine};
+    use sam_core::entities::aliases::Alias;
+    use sam_core::entities::choices::Choice;
+    use sam_core::entities::namespaces::NamespaceUpdater;
+    use sam_core::entities::vars::Var;
+    use sam_persistence::repositories::{AliasesRepository, VarsRepository};
+    use sam_persistence::{RustBreakCache, ShellKind, VarsCache};
+    use sam_utils::fsutils::TempFile;
+    use std::time::Duration;
+
+    #[test]
+    fn test_candidates() {
+        let mut alias = Alias::new("show", "desc", "cat {{ dir }}/{{ file }}");
+        NamespaceUpdater::update(&mut alias, "ns");
+        let mut dir = Var::new(
+            "dir",
+            "desc",
+            vec![Choice::from_value("/etc"), Choice::from_value("/usr")],
+        );
+        dir.update("ns");
+        let mut file = Var::from_command("file", "desc", "ls {{ dir }}");
+        file.update("ns");
+        let mut token =
+            Var::new("token", "desc", vec![Choice::from_value("s3cr3t")]).with_secret(true);
+        token.update("ns");
+
+        let tmp = TempFile::new().expect("can't create a temporary file");
+        let ttl = Duration::from_secs(90);
+        let cache = RustBreakCache::with_ttl(&tmp.path, &ttl).expect("can't open the cache");
+        cache
+            .put(&file.name(), &"ls /etc", &"hosts\tthe hosts\nfstab")
+            .expect("can't write in the cache");
+
+        let engine = CompletionEngine {
+            aliases: AliasesRepository::new(vec![alias].into_iter()).unwrap(),
+            vars: VarsRepository::new(vec![dir, file, token].into_iter()),
+            cache_dir: tmp.path.clone(),
+            ttl,
+            output: Box::new(std::io::sink()),
+        };
+        let candidates = |kind, prefix| -> Vec<String> {
+            engine
+                .candidates(kind, prefix)
+                .expect("should list the candidates")
+                .into_iter()
+                .collect()
+        };
+        assert_eq!(candidates(CandidateKind::Aliases, "ns::"), vec!["ns::show"]);
+        assert_eq!(
+            candidates(CandidateKind::Aliases, "other"),
+            Vec::<String>::new()
+        );
+        assert_eq!(candidates(CandidateKind::Vars, "ns::f"), vec!["ns::file"]);
+        assert_eq!(
+            candidates(CandidateKind::Choices, "ns::d"),
+            vec!["ns::dir="]
+        );
+        assert_eq!(
+            candidates(CandidateKind::Choices, "ns::dir=/e"),
+            vec!["ns::dir=/etc"]
+        );
+        assert_eq!(
+            candidates(CandidateKind::Choices, "ns::file="),
+            vec!["ns::file=fstab", "ns::file=hosts"]
+        );
+        assert_eq!(
+            candidates(CandidateKind::Choices, "ns::token="),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_write_script() {
+        let mut output = vec![];
+        write_script(ShellKind::Bash, &mut output).expect("should write the script");
+        let script = String::from_utf8(output).unwrap();
+        assert!(script.contains("_sam_clap() {"));
+        assert!(script.contains("complete -F _sam -o bashdefault -o default sam"));
+        assert!(script.contains("sam __complete \"${kind}\" \"${cur}\""));
+        assert!(!script.contains("__complete)"));
+
+        let mut output = vec![];
+        write_script(ShellKind::Zsh, &mut output).expect("should write the script");
+        let script = String::from_utf8(output).unwrap();
+        assert!(script.starts_with("#compdef sam"));
+        assert!(script.ends_with("compadd -a candidates\n}\n\n_sam \"$@\"\n"));
+    }
+
+    #[test]
+    fn test_write_widget() {
+        let widget = |shell| {
+            let mut output = vec![];
+            write_widget(shell, &mut output).expect("should write the widget");
+            String::from_utf8(output).unwrap()
+        };
+        assert!(widget(ShellKind::Bash).contains("READLINE_LINE=\"${cmd}\""));
+        assert!(widget(ShellKind::Bash).ends_with("bind -x '\"\\es\": __sam_widget'\n"));
+        assert!(widget(ShellKind::Zsh).contains("bindkey '\\es' __sam_widget\n"));
+        assert!(widget(ShellKind::Fish).contains("commandline -r -- $cmd\n"));
+    }
+}
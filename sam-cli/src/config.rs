@@ -1,12 +1,14 @@
 use crate::cli::CLISettings;
+use crate::env_values::{resolve_env, EnvValue, ErrorsEnvValues};
 use sam_core::entities::choices::Choice;
 use sam_core::entities::identifiers::Identifier;
-use sam_persistence::CacheError;
+use sam_persistence::{CacheError, ShellHistory, ShellKind};
+use sam_readers::{is_namespace_file, FileFormat};
 use sam_utils::fsutils;
-use sam_utils::fsutils::walk_dir;
-use sam_utils::fsutils::ErrorsFS;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use sam_utils::fsutils::{walk_dir_with, ErrorsFS, WalkFilter};
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Display;
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
@@ -14,58 +16,168 @@ use std::time::Duration;
 use thiserror::Error;
 
 const CONFIG_FILE_NAME: &str = ".sam_rc.toml";
+const SYSTEM_CONFIG_FILE: &str = "/etc/sam/config.toml";
+const DEFAULT_TTL: u64 = 1800;
+const XDG_CONFIG_FILE_NAME: &str = "config.toml";
+const ENV_CONFIG: &str = "SAM_CONFIG";
+const ENV_CACHE_DIR: &str = "SAM_CACHE_DIR";
+const ENV_HISTORY_FILE: &str = "SAM_HISTORY_FILE";
+const ENV_PROFILE: &str = "SAM_PROFILE";
+const ENV_XDG_CONFIG_HOME: &str = "XDG_CONFIG_HOME";
+const ENV_XDG_CACHE_HOME: &str = "XDG_CACHE_HOME";
+const ENV_XDG_DATA_HOME: &str = "XDG_DATA_HOME";
 
-#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+/// the content of a single configuration file. Every setting is optional
+/// so that a file only holds what it adds to, or overrides in, the previous layers.
+#[derive(Debug, Deserialize, Default)]
+struct ConfigLayer {
+    #[serde(default)]
+    root_dir: Vec<PathBuf>,
+    ttl: Option<u64>,
+    /// the shell whose history file executed commands are appended to, off by default.
+    shell_history: Option<ShellKind>,
+    shell_history_file: Option<String>,
+    /// globs restricting the files read from the root directories,
+    /// matched against their path relative to the root.
+    #[serde(default)]
+    include: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+    /// `[profile.<name>]` tables, overlaid on the base settings when the profile is selected.
+    #[serde(default)]
+    profile: HashMap<String, ConfigLayer>,
+    /// the environment variables made available to the aliases and vars.
+    #[serde(default)]
+    env: HashMap<String, EnvValue>,
+    /// any other key, read as an environment variable for backward compatibility.
+    #[serde(flatten)]
+    unknown: HashMap<String, toml::Value>,
+}
+
+/// where the effective value of a setting comes from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Origin {
+    Default,
+    File(PathBuf),
+    Profile(PathBuf, String),
+    Environment(&'static str),
+    CommandLine,
+}
+
+impl Display for Origin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Origin::Default => write!(f, "default"),
+            Origin::File(path) => write!(f, "{}", path.display()),
+            Origin::Profile(path, profile) => {
+                write!(f, "{} [profile.{}]", path.display(), profile)
+            }
+            Origin::Environment(variable) => write!(f, "${}", variable),
+            Origin::CommandLine => write!(f, "command line"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setting {
+    pub key: String,
+    pub value: String,
+    pub origin: Origin,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Origins {
+    root_dir: Vec<Origin>,
+    include: Vec<Origin>,
+    exclude: Vec<Origin>,
+    values: HashMap<String, Origin>,
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct AppSettings {
     root_dir: Vec<PathBuf>,
     ttl: u64,
-    #[serde(flatten)]
+    shell_history: Option<ShellKind>,
+    shell_history_file: Option<String>,
+    include: Vec<String>,
+    exclude: Vec<String>,
     pub env_variables: HashMap<String, String>,
-    #[serde(skip)]
+    env_definitions: HashMap<String, EnvValue>,
+    deprecated_env_keys: Vec<(Origin, String)>,
+    profile: Option<String>,
+    profiles: BTreeSet<String>,
+    profile_file: PathBuf,
+    // the file given with `--config`.
+    config_file: Option<PathBuf>,
+    origins: Origins,
+    walk_filter: WalkFilter,
     cache_dir: PathBuf,
-    #[serde(skip)]
     history_file: PathBuf,
-    #[serde(skip)]
     pub dry: bool,
-    #[serde(skip)]
+    pub print: bool,
+    pub non_interactive: bool,
     pub silent: bool,
-    #[serde(skip)]
     pub no_cache: bool,
-    #[serde(skip)]
     pub defaults: HashMap<Identifier, Choice>,
 }
 
 type Result<T> = std::result::Result<T, ErrorsSettings>;
 
 impl AppSettings {
-    fn read_config(path: PathBuf) -> Result<AppSettings> {
+    fn read_config(path: PathBuf) -> Result<ConfigLayer> {
         let path = fsutils::ensure_exists(path)
             .and_then(fsutils::ensure_is_file)
             .and_then(fsutils::ensure_sufficient_permisions)?;
         let content = fs::read_to_string(&path)?;
-        let conf: AppSettings = toml::from_str(content.as_str())?;
-        Ok(conf)
+        toml::from_str(content.as_str()).map_err(|e| ErrorsSettings::CantDeserialize(path, e))
     }
 
+    /// reads and merges, in this order, the system configuration, the user's one,
+    /// the `.sam_rc.toml` files from the root directory down to the current one,
+    /// the file named by `SAM_CONFIG` and finally the one given with `--config`.
     pub fn load(cli_settings: Option<CLISettings>) -> Result<Self> {
-        let home_dir_o = Self::home_dir_config_path()?;
-        let current_dir_o = Self::current_dir_config_path();
-
-        let config_home_dir = Self::read_config(home_dir_o);
-        let config_current_dir = current_dir_o.and_then(Self::read_config);
+        let cli_config = cli_settings.as_ref().and_then(|s| s.config.clone());
+        let cli_profile = cli_settings.as_ref().and_then(|s| s.profile.clone());
+        let mut settings = AppSettings {
+            ttl: DEFAULT_TTL,
+            ..AppSettings::default()
+        };
+        let mut loaded: Vec<PathBuf> = vec![];
+        let mut profile_layers: Vec<(PathBuf, HashMap<String, ConfigLayer>)> = vec![];
+        settings.config_file = cli_config.clone();
+        for (path, required) in Self::layer_paths(cli_config)? {
+            if !required && !path.is_file() {
+                continue;
+            }
+            let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+            if loaded.contains(&canonical) {
+                continue;
+            }
+            let mut layer = Self::read_config(path.clone())?;
+            let profiles = std::mem::take(&mut layer.profile);
+            settings.merge(layer, &path, Origin::File(path.clone()))?;
+            settings.profiles.extend(profiles.keys().cloned());
+            profile_layers.push((path, profiles));
+            loaded.push(canonical);
+        }
+        if loaded.is_empty() {
+            return Err(ErrorsSettings::NoConfigFile(Self::home_dir_config_path()?));
+        }
 
-        let cache_dir = Self::cache_dir_path()?;
-        let history_file = Self::history_file_path()?;
+        settings.cache_dir = Self::cache_dir_path()?;
+        settings.history_file = Self::history_file_path()?;
+        settings.track_env_override(ENV_CACHE_DIR, "cache_dir");
+        settings.track_env_override(ENV_HISTORY_FILE, "history_file");
 
-        let mut settings = config_current_dir
-            .or(config_home_dir)
-            .and_then(AppSettings::validate)
-            .map(|mut e| {
-                e.cache_dir = cache_dir;
-                e.history_file = history_file;
-                e
-            })?;
+        settings.profile_file = Self::profile_file_path()?;
+        if let Some((profile, origin)) = Self::selected_profile(cli_profile)? {
+            settings.apply_profile(profile, origin, profile_layers)?;
+        }
+        Self::ensure_parent_exists(&settings.cache_dir)?;
+        Self::ensure_parent_exists(&settings.history_file)?;
+        settings.warn_deprecated_env_keys();
 
+        let mut settings = AppSettings::validate(settings)?;
         if let Some(m) = cli_settings {
             settings.merge_command_line_args(m);
         }
@@ -73,8 +185,287 @@ impl AppSettings {
         Ok(settings)
     }
 
+    /// computes the values of the `[env]` section, running their `from_command` if any.
+    pub fn resolve_env_variables(&mut self) -> Result<()> {
+        self.env_variables = resolve_env(&self.env_definitions)?;
+        Ok(())
+    }
+
+    /// the profile given with `--profile`, or else the one named by `SAM_PROFILE`,
+    /// or else the one persisted with `sam config use-profile`.
+    fn selected_profile(cli_profile: Option<String>) -> Result<Option<(String, Origin)>> {
+        if let Some(profile) = cli_profile {
+            return Ok(Some((profile, Origin::CommandLine)));
+        }
+        if let Ok(profile) = std::env::var(ENV_PROFILE) {
+            return Ok(
+                Some((profile, Origin::Environment(ENV_PROFILE))).filter(|p| !p.0.is_empty())
+            );
+        }
+        let profile_file = Self::profile_file_path()?;
+        let persisted = fs::read_to_string(&profile_file)
+            .ok()
+            .map(|content| content.trim().to_string())
+            .filter(|profile| !profile.is_empty());
+        Ok(persisted.map(|profile| (profile, Origin::File(profile_file))))
+    }
+
+    /// overlays the `[profile.<name>]` tables of every layer, in the order the layers were read,
+    /// and keeps the cache and the history of the profile apart from the other ones.
+    fn apply_profile(
+        &mut self,
+        profile: String,
+        origin: Origin,
+        profile_layers: Vec<(PathBuf, HashMap<String, ConfigLayer>)>,
+    ) -> Result<()> {
+        // a persisted profile may have been removed from the configuration since, failing
+        // would prevent `sam config use-profile` from clearing it.
+        if !self.profiles.contains(&profile) && matches!(origin, Origin::File(_)) {
+            eprintln!(
+                "{}warning:{} the profile `{}` persisted in {} no longer exists, it is ignored",
+                termion::color::Fg(termion::color::Yellow),
+                termion::style::Reset,
+                profile,
+                origin,
+            );
+            return Ok(());
+        }
+        if !self.profiles.contains(&profile) {
+            return Err(ErrorsSettings::UnknownProfile(
+                profile,
+                self.profiles.iter().cloned().collect(),
+            ));
+        }
+        for (path, mut profiles) in profile_layers {
+            if let Some(layer) = profiles.remove(&profile) {
+                if !layer.profile.is_empty() {
+                    return Err(ErrorsSettings::NestedProfile(path, profile));
+                }
+                let layer_origin = Origin::Profile(path.clone(), profile.clone());
+                self.merge(layer, &path, layer_origin)?;
+            }
+        }
+        self.cache_dir = Self::profile_path(&self.cache_dir, &profile);
+        self.history_file = Self::profile_path(&self.history_file, &profile);
+        self.origins.values.insert("profile".into(), origin);
+        self.profile = Some(profile);
+        Ok(())
+    }
+
+    fn profile_path(path: &Path, profile: &str) -> PathBuf {
+        let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(format!("-{}", profile));
+        path.with_file_name(file_name)
+    }
+
+    /// the file holding the profile persisted with `sam config use-profile`.
+    fn profile_file_path() -> Result<PathBuf> {
+        Ok(Self::xdg_dir(ENV_XDG_DATA_HOME, &[".local", "share"])?
+            .join("sam")
+            .join("profile"))
+    }
+
+    pub fn config_file(&self) -> Option<&Path> {
+        self.config_file.as_deref()
+    }
+
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    pub fn profiles(&self) -> impl Iterator<Item = &String> + '_ {
+        self.profiles.iter()
+    }
+
+    pub fn profile_file(&self) -> &'_ Path {
+        self.profile_file.as_ref()
+    }
+
+    fn layer_paths(cli_config: Option<PathBuf>) -> Result<Vec<(PathBuf, bool)>> {
+        let mut paths = vec![
+            (PathBuf::from(SYSTEM_CONFIG_FILE), false),
+            (Self::home_dir_config_path()?, false),
+        ];
+        let current_dir =
+            std::env::current_dir().map_err(|_| ErrorsSettings::CantFindCurrentDirectory)?;
+        let mut ancestors: Vec<&Path> = current_dir.ancestors().collect();
+        ancestors.reverse();
+        paths.extend(
+            ancestors
+                .into_iter()
+                .map(|dir| (dir.join(CONFIG_FILE_NAME), false)),
+        );
+        paths.extend(Self::env_path(ENV_CONFIG).map(|path| (path, true)));
+        paths.extend(cli_config.map(|path| (path, true)));
+        Ok(paths)
+    }
+
+    /// `root_dir`, `include` and `exclude` entries are appended to the ones of the previous
+    /// layers, `ttl` and the shell history settings are overridden when the layer sets them
+    /// and env variables are merged, the layer's value winning for keys defined twice.
+    /// Relative `root_dir` entries are resolved against the directory of the layer's file.
+    fn merge(&mut self, layer: ConfigLayer, path: &Path, origin: Origin) -> Result<()> {
+        let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+        for root_dir in layer.root_dir {
+            let expanded = PathBuf::from(fsutils::replace_home_variable(
+                root_dir.to_string_lossy().into(),
+            ));
+            let root_dir: PathBuf = base_dir.join(expanded).components().collect();
+            if !self.root_dir.contains(&root_dir) {
+                self.root_dir.push(root_dir);
+                self.origins.root_dir.push(origin.clone());
+            }
+        }
+        for glob in layer.include {
+            self.include.push(glob);
+            self.origins.include.push(origin.clone());
+        }
+        for glob in layer.exclude {
+            self.exclude.push(glob);
+            self.origins.exclude.push(origin.clone());
+        }
+        if let Some(ttl) = layer.ttl {
+            self.ttl = ttl;
+            self.origins.values.insert("ttl".into(), origin.clone());
+        }
+        if let Some(shell_history) = layer.shell_history {
+            self.shell_history = Some(shell_history);
+            self.origins
+                .values
+                .insert("shell_history".into(), origin.clone());
+        }
+        if let Some(shell_history_file) = layer.shell_history_file {
+            self.shell_history_file = Some(shell_history_file);
+            self.origins
+                .values
+                .insert("shell_history_file".into(), origin.clone());
+        }
+        for (key, value) in layer.unknown {
+            match value {
+                toml::Value::String(value) => {
+                    self.deprecated_env_keys.push((origin.clone(), key.clone()));
+                    self.insert_env(key, EnvValue::Literal(value), &origin);
+                }
+                _ => return Err(ErrorsSettings::UnknownSetting(path.to_owned(), key)),
+            }
+        }
+        for (key, value) in layer.env {
+            self.insert_env(key, value, &origin);
+        }
+        Ok(())
+    }
+
+    fn insert_env(&mut self, key: String, value: EnvValue, origin: &Origin) {
+        self.origins
+            .values
+            .insert(format!("env.{}", key), origin.clone());
+        self.env_definitions.insert(key, value);
+    }
+
+    fn warn_deprecated_env_keys(&self) {
+        let mut by_origin: Vec<(String, Vec<&str>)> = vec![];
+        for (origin, key) in &self.deprecated_env_keys {
+            let origin = origin.to_string();
+            match by_origin.iter_mut().find(|(o, _)| o == &origin) {
+                Some((_, keys)) => keys.push(key),
+                None => by_origin.push((origin, vec![key])),
+            }
+        }
+        for (origin, mut keys) in by_origin {
+            keys.sort_unstable();
+            eprintln!(
+                "{}warning:{} the top-level keys {} of {} are read as environment variables, \
+                 this is deprecated, please move them to an [env] table",
+                termion::color::Fg(termion::color::Yellow),
+                termion::style::Reset,
+                keys.join(", "),
+                origin,
+            );
+        }
+    }
+
+    fn track_env_override(&mut self, variable: &'static str, key: &str) {
+        if Self::env_path(variable).is_some() {
+            self.origins
+                .values
+                .insert(key.into(), Origin::Environment(variable));
+        }
+    }
+
+    /// every effective setting along with where its value comes from.
+    pub fn effective_settings(&self) -> Vec<Setting> {
+        let origin_of = |key: &str| {
+            self.origins
+                .values
+                .get(key)
+                .cloned()
+                .unwrap_or(Origin::Default)
+        };
+        let setting = |key: &str, value: String, origin: Origin| Setting {
+            key: key.to_string(),
+            value,
+            origin,
+        };
+        let mut settings = vec![];
+        if let Some(profile) = &self.profile {
+            settings.push(setting(
+                "profile",
+                format!("{:?}", profile),
+                origin_of("profile"),
+            ));
+        }
+        for (root_dir, origin) in self.root_dir.iter().zip(&self.origins.root_dir) {
+            settings.push(setting(
+                "root_dir",
+                format!("{:?}", root_dir),
+                origin.clone(),
+            ));
+        }
+        settings.push(setting("ttl", self.ttl.to_string(), origin_of("ttl")));
+        if let Some(shell_history) = &self.shell_history {
+            let value = format!("{:?}", format!("{:?}", shell_history).to_lowercase());
+            settings.push(setting("shell_history", value, origin_of("shell_history")));
+        }
+        if let Some(shell_history_file) = &self.shell_history_file {
+            let value = format!("{:?}", shell_history_file);
+            settings.push(setting(
+                "shell_history_file",
+                value,
+                origin_of("shell_history_file"),
+            ));
+        }
+        for (glob, origin) in self.include.iter().zip(&self.origins.include) {
+            settings.push(setting("include", format!("{:?}", glob), origin.clone()));
+        }
+        for (glob, origin) in self.exclude.iter().zip(&self.origins.exclude) {
+            settings.push(setting("exclude", format!("{:?}", glob), origin.clone()));
+        }
+        let mut env_definitions: Vec<(&String, &EnvValue)> = self.env_definitions.iter().collect();
+        env_definitions.sort_by_key(|(key, _)| *key);
+        for (key, definition) in env_definitions {
+            // commands are shown rather than their output, which may well be a secret.
+            let value = match (definition, self.env_variables.get(key)) {
+                (EnvValue::Literal(_), Some(value)) => format!("{:?}", value),
+                _ => definition.to_string(),
+            };
+            let key = format!("env.{}", key);
+            settings.push(setting(&key, value, origin_of(&key)));
+        }
+        let cache_dir = format!("{:?}", self.cache_dir);
+        settings.push(setting("cache_dir", cache_dir, origin_of("cache_dir")));
+        let history_file = format!("{:?}", self.history_file);
+        settings.push(setting(
+            "history_file",
+            history_file,
+            origin_of("history_file"),
+        ));
+        settings
+    }
+
     fn merge_command_line_args(&mut self, cmd_args: CLISettings) {
         self.dry = cmd_args.dry;
+        self.print = cmd_args.print;
+        self.non_interactive = cmd_args.non_interactive;
         self.silent = cmd_args.silent;
         self.no_cache = cmd_args.no_cache;
         self.defaults = cmd_args.default_choices.0;
@@ -92,77 +483,140 @@ impl AppSettings {
         self.history_file.as_ref()
     }
 
-    fn validate(orig: AppSettings) -> Result<AppSettings> {
+    pub fn shell_history(&self) -> Result<Option<ShellHistory>> {
+        let kind = match self.shell_history {
+            Some(kind) => kind,
+            None => return Ok(None),
+        };
+        let path = match &self.shell_history_file {
+            Some(path) => PathBuf::from(fsutils::replace_home_variable(path.clone())),
+            None => dirs::home_dir()
+                .map(|home| ShellHistory::default_path(kind, &home))
+                .ok_or(ErrorsSettings::CantFindHomeDirectory)?,
+        };
+        Ok(Some(ShellHistory::new(kind, path)))
+    }
+
+    fn validate(mut orig: AppSettings) -> Result<AppSettings> {
+        orig.walk_filter = WalkFilter::new(&orig.include, &orig.exclude)?;
         for path in &orig.root_dir {
-            if let Ok(files) = fsutils::walk_dir(path) {
-                for f in files {
-                    fsutils::ensure_exists(f).and_then(fsutils::ensure_sufficient_permisions)?;
-                }
+            let files = match fsutils::walk_dir_with(path, &orig.walk_filter) {
+                Ok(files) => files,
+                // a missing or unreadable root directory doesn't hold any alias, an invalid
+                // `.samignore` would silently hide all of them.
+                Err(ErrorsFS::UnexpectedIOError(_)) => continue,
+                Err(err) => return Err(err.into()),
+            };
+            for f in files {
+                fsutils::ensure_exists(f).and_then(fsutils::ensure_sufficient_permisions)?;
             }
         }
         Ok(orig)
     }
 
+    /// `$XDG_CONFIG_HOME/sam/config.toml` when it exists, `~/.sam_rc.toml` otherwise.
     fn home_dir_config_path() -> Result<PathBuf> {
+        let xdg_config = Self::xdg_dir(ENV_XDG_CONFIG_HOME, &[".config"])?
+            .join("sam")
+            .join(XDG_CONFIG_FILE_NAME);
+        if xdg_config.is_file() {
+            return Ok(xdg_config);
+        }
         dirs::home_dir()
             .map(|e| e.join(CONFIG_FILE_NAME))
             .ok_or(ErrorsSettings::CantFindHomeDirectory)
     }
 
     fn cache_dir_path() -> Result<PathBuf> {
-        dirs::home_dir()
-            .map(|e| e.join(".cache").join("sam"))
-            .ok_or(ErrorsSettings::CantFindCacheDirectory)
+        match Self::env_path(ENV_CACHE_DIR) {
+            Some(path) => Ok(path),
+            None => Ok(Self::xdg_dir(ENV_XDG_CACHE_HOME, &[".cache"])?.join("sam")),
+        }
     }
 
     fn history_file_path() -> Result<PathBuf> {
-        dirs::home_dir()
-            .map(|e| e.join(".local").join("share").join("sam").join("history"))
-            .ok_or(ErrorsSettings::CantFindCacheDirectory)
+        match Self::env_path(ENV_HISTORY_FILE) {
+            Some(path) => Ok(path),
+            None => Ok(Self::xdg_dir(ENV_XDG_DATA_HOME, &[".local", "share"])?
+                .join("sam")
+                .join("history")),
+        }
     }
 
-    fn current_dir_config_path() -> Result<PathBuf> {
-        std::env::current_dir()
-            .map_err(|_| ErrorsSettings::CantFindCurrentDirectory)
-            .map(|e| e.join(CONFIG_FILE_NAME))
+    /// the directory named by an XDG variable, relative values are ignored
+    /// as the specification requires, in which case `~/<default>` is used.
+    fn xdg_dir(variable: &str, default: &[&str]) -> Result<PathBuf> {
+        match std::env::var_os(variable).map(PathBuf::from) {
+            Some(dir) if dir.is_absolute() => Ok(dir),
+            _ => dirs::home_dir()
+                .map(|home| default.iter().fold(home, |dir, part| dir.join(part)))
+                .ok_or(ErrorsSettings::CantFindHomeDirectory),
+        }
+    }
+
+    fn ensure_parent_exists(path: &Path) -> Result<()> {
+        match path.parent() {
+            Some(parent) if !parent.exists() => fs::create_dir_all(parent)
+                .map_err(|e| ErrorsSettings::CantCreateDirectory(parent.to_owned(), e)),
+            _ => Ok(()),
+        }
+    }
+
+    fn env_path(variable: &str) -> Option<PathBuf> {
+        std::env::var(variable)
+            .ok()
+            .filter(|value| !value.is_empty())
+            .map(|value| PathBuf::from(fsutils::replace_home_variable(value)))
     }
+
+    pub fn root_dirs(&self) -> &[PathBuf] {
+        &self.root_dir
+    }
+
     pub fn variables(&self) -> HashMap<String, String> {
         self.env_variables.clone()
     }
 
+    // the errors that matter were reported by `validate`.
     fn sam_files(&self) -> impl Iterator<Item = PathBuf> + '_ {
         self.root_dir
             .iter()
             .map(AsRef::as_ref)
-            .flat_map(walk_dir)
+            .flat_map(|root| walk_dir_with(root, &self.walk_filter))
             .flatten()
     }
 
     pub fn aliases_files(&self) -> impl Iterator<Item = PathBuf> + '_ {
         self.sam_files().filter(|f| {
-            if let Some(file_name) = f.file_name() {
-                file_name == "aliases.yaml" || file_name == "aliases.yml"
-            } else {
-                false
-            }
+            let aliases_file = f.file_stem().map(|s| s == "aliases").unwrap_or(false)
+                && FileFormat::from_path(f).is_some();
+            aliases_file || is_namespace_file(f)
         })
     }
 
     pub fn vars_files(&self) -> impl Iterator<Item = PathBuf> + '_ {
         self.sam_files().filter(|f| {
-            if let Some(file_name) = f.file_name() {
-                file_name == "vars.yaml" || file_name == "vars.yml"
-            } else {
-                false
-            }
+            let vars_file = f.file_stem().map(|s| s == "vars").unwrap_or(false)
+                && FileFormat::from_path(f).is_some();
+            vars_file || is_namespace_file(f)
         })
     }
 }
 
 #[derive(Debug, Error)]
 pub enum ErrorsSettings {
-    #[error("got deserialize the configuration file because\n-> {0}")]
-    CantDeserialize(#[from] toml::de::Error),
+    #[error("could not deserialize the configuration file {0} because\n-> {1}")]
+    CantDeserialize(PathBuf, toml::de::Error),
+    #[error("the profile `{0}` is not defined, the available profiles are {1:?}")]
+    UnknownProfile(String, Vec<String>),
+    #[error("the profile `{1}` of {0} can't define profiles itself")]
+    NestedProfile(PathBuf, String),
+    #[error("unknown setting `{1}` in {0}, environment variables belong to the [env] table")]
+    UnknownSetting(PathBuf, String),
+    #[error("could not compute the env variables\n-> {0}")]
+    Env(#[from] ErrorsEnvValues),
+    #[error("no configuration file was found, you can create one at {0}")]
+    NoConfigFile(PathBuf),
     #[error("can't read the configuration file because\n-> {0}")]
     CantReadConfigFile(#[from] io::Error),
     #[error("got the following file-system related error\n-> {0}")]
@@ -173,6 +627,157 @@ pub enum ErrorsSettings {
     CantFindHomeDirectory,
     #[error("we were unable to locate the cache directory for the current user")]
     CantFindCacheDirectory,
+    #[error("could not create the directory {0}\n-> {1}")]
+    CantCreateDirectory(PathBuf, io::Error),
     #[error("we were unable to locate the current directory for the current user")]
     CantFindCurrentDirectory,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{resolve_env, AppSettings, ConfigLayer, EnvValue, Origin};
+    use sam_utils::fsutils::{TempDirectory, IGNORE_FILE_NAME};
+    use std::path::{Path, PathBuf};
+
+    fn layer(content: &str) -> ConfigLayer {
+        toml::from_str(content).expect("the layer should deserialize")
+    }
+
+    #[test]
+    fn test_merge_layers() {
+        let home = Path::new("/home/user/.sam_rc.toml");
+        let project = Path::new("/home/user/project/.sam_rc.toml");
+        let mut settings = AppSettings::default();
+        settings
+            .merge(
+                layer(
+                    "root_dir=[\"/home/user/aliases\"]\nttl=1800\nPAGER=\"less\"\nEDITOR=\"vim\"",
+                ),
+                home,
+                Origin::File(home.to_owned()),
+            )
+            .expect("the layer should merge");
+        settings
+            .merge(
+                layer(
+                    "root_dir=[\"./aliases\", \"/home/user/aliases\"]\nttl=60\n\
+                 [env]\nEDITOR=\"nano\"\nTOKEN={ from_command=\"echo secret\" }",
+                ),
+                project,
+                Origin::File(project.to_owned()),
+            )
+            .expect("the layer should merge");
+
+        assert_eq!(
+            settings.root_dirs(),
+            &[
+                PathBuf::from("/home/user/aliases"),
+                PathBuf::from("/home/user/project/aliases"),
+            ]
+        );
+        assert_eq!(settings.ttl, 60);
+        assert_eq!(settings.deprecated_env_keys.len(), 2);
+        settings.env_variables =
+            resolve_env(&settings.env_definitions).expect("the env should resolve");
+        assert_eq!(settings.env_variables["PAGER"], "less");
+        assert_eq!(settings.env_variables["EDITOR"], "nano");
+        assert_eq!(settings.env_variables["TOKEN"], "secret");
+
+        let effective = settings.effective_settings();
+        assert_eq!(effective[5].value, "{ from_command = \"echo secret\" }");
+        let origins: Vec<(String, Origin)> =
+            effective.into_iter().map(|s| (s.key, s.origin)).collect();
+        let home = Origin::File(home.to_owned());
+        let project = Origin::File(project.to_owned());
+        assert_eq!(origins[0], (String::from("root_dir"), home.clone()));
+        assert_eq!(origins[1], (String::from("root_dir"), project.clone()));
+        assert_eq!(origins[2], (String::from("ttl"), project.clone()));
+        assert_eq!(origins[3], (String::from("env.EDITOR"), project));
+        assert_eq!(origins[4], (String::from("env.PAGER"), home));
+        assert_eq!(origins[6], (String::from("cache_dir"), Origin::Default));
+
+        let mut settings = AppSettings::default();
+        let typo = settings.merge(
+            layer("ttll=10"),
+            Path::new("/.sam_rc.toml"),
+            Origin::Default,
+        );
+        assert!(typo.is_err());
+    }
+
+    #[test]
+    fn test_apply_profile() {
+        let path = PathBuf::from("/home/user/.sam_rc.toml");
+        let mut base = layer(
+            "root_dir=[\"/aliases\"]\nttl=1800\nPAGER=\"less\"\n\
+             [profile.work]\nroot_dir=[\"/work\"]\nttl=60\n\
+             [profile.oncall.env]\nPAGER=\"cat\"",
+        );
+        let profiles = std::mem::take(&mut base.profile);
+        let mut settings = AppSettings {
+            cache_dir: PathBuf::from("/home/user/.cache/sam"),
+            history_file: PathBuf::from("/home/user/.local/share/sam/history"),
+            ..AppSettings::default()
+        };
+        settings
+            .merge(base, &path, Origin::File(path.clone()))
+            .expect("the layer should merge");
+        settings.profiles.extend(profiles.keys().cloned());
+
+        let mut unknown = settings.clone();
+        assert!(unknown
+            .apply_profile(String::from("home"), Origin::CommandLine, vec![])
+            .is_err());
+        let profile_file = PathBuf::from("/home/user/.local/share/sam/profile");
+        unknown
+            .apply_profile(String::from("home"), Origin::File(profile_file), vec![])
+            .expect("a stale persisted profile should be ignored");
+        assert_eq!(unknown.profile(), None);
+        assert_eq!(unknown.cache_dir(), settings.cache_dir());
+
+        settings
+            .apply_profile(
+                String::from("work"),
+                Origin::CommandLine,
+                vec![(path.clone(), profiles)],
+            )
+            .expect("the profile should apply");
+        assert_eq!(
+            settings.root_dirs(),
+            &[PathBuf::from("/aliases"), PathBuf::from("/work")]
+        );
+        assert_eq!(settings.ttl, 60);
+        assert_eq!(
+            settings.env_definitions["PAGER"],
+            EnvValue::Literal(String::from("less"))
+        );
+        assert_eq!(settings.profile(), Some("work"));
+        assert_eq!(
+            settings.cache_dir(),
+            Path::new("/home/user/.cache/sam-work")
+        );
+        assert_eq!(
+            settings.history_file(),
+            Path::new("/home/user/.local/share/sam/history-work")
+        );
+        assert_eq!(
+            settings.origins.values["ttl"],
+            Origin::Profile(path, String::from("work"))
+        );
+    }
+
+    #[test]
+    fn test_validate_reports_invalid_ignore_files() {
+        let root = TempDirectory::new().expect("can't create temp dir for test");
+        std::fs::write(root.path.join("aliases.yaml"), "").unwrap();
+        let settings = AppSettings {
+            root_dir: vec![root.path.clone(), root.path.join("missing")],
+            ..AppSettings::default()
+        };
+        assert!(AppSettings::validate(settings.clone()).is_ok());
+
+        std::fs::write(root.path.join(IGNORE_FILE_NAME), "drafts/a[\n").unwrap();
+        let err = AppSettings::validate(settings).unwrap_err();
+        assert!(err.to_string().contains(IGNORE_FILE_NAME), "{}", err);
+    }
+}
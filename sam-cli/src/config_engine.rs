@@ -1,8 +1,11 @@
+use crate::config::Setting;
 use sam_core::entities::commands::programs_used;
 use sam_core::entities::commands::unset_env_vars;
 use sam_persistence::repositories::AliasesRepository;
 use sam_persistence::repositories::VarsRepository;
 use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
 use thiserror::Error;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -12,12 +15,23 @@ pub enum ConfigCommand {
     #[allow(dead_code)]
     CheckUnavailablePrograms,
     All,
+    Show {
+        origin: bool,
+    },
+    Profiles,
+    UseProfile {
+        profile: Option<String>,
+    },
 }
 
 pub struct ConfigEngine {
     pub aliases: AliasesRepository,
     pub vars: VarsRepository,
     pub env_variables: HashMap<String, String>,
+    pub settings: Vec<Setting>,
+    pub profiles: Vec<String>,
+    pub profile: Option<String>,
+    pub profile_file: PathBuf,
 }
 
 impl ConfigEngine {
@@ -29,8 +43,43 @@ impl ConfigEngine {
                 self.check_unavailable_programs()?;
                 self.check_unset_env_vars()
             }
+            ConfigCommand::Show { origin } => {
+                for line in show_settings(&self.settings, origin) {
+                    println!("{}", line);
+                }
+                Ok(0)
+            }
+            ConfigCommand::Profiles => {
+                for profile in &self.profiles {
+                    let marker = if self.profile.as_ref() == Some(profile) {
+                        "*"
+                    } else {
+                        " "
+                    };
+                    println!("{} {}", marker, profile);
+                }
+                Ok(0)
+            }
+            ConfigCommand::UseProfile { profile } => self.use_profile(profile),
         }
     }
+
+    fn use_profile(&self, profile: Option<String>) -> Result<i32> {
+        match profile {
+            Some(profile) => {
+                if !self.profiles.contains(&profile) {
+                    return Err(ErrorsConfigEngine::UnknownProfile(profile));
+                }
+                if let Some(parent) = self.profile_file.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&self.profile_file, format!("{}\n", profile))?;
+            }
+            None if self.profile_file.exists() => fs::remove_file(&self.profile_file)?,
+            None => {}
+        }
+        Ok(0)
+    }
     fn check_unset_env_vars(&self) -> Result<i32> {
         let missing_envvars_in_aliases = unset_env_vars(self.aliases.aliases().iter());
         let missing_envvars_in_vars = unset_env_vars(self.vars.vars_iter());
@@ -89,7 +138,62 @@ impl ConfigEngine {
     }
 }
 
+fn show_settings(settings: &[Setting], origin: bool) -> Vec<String> {
+    let lines: Vec<String> = settings
+        .iter()
+        .map(|s| format!("{} = {}", s.key, s.value))
+        .collect();
+    if !origin {
+        return lines;
+    }
+    let width = lines.iter().map(|l| l.len()).max().unwrap_or_default();
+    lines
+        .into_iter()
+        .zip(settings)
+        .map(|(line, s)| format!("{:<width$}  # {}", line, s.origin, width = width))
+        .collect()
+}
+
 type Result<T> = std::result::Result<T, ErrorsConfigEngine>;
 
 #[derive(Debug, Error)]
-pub enum ErrorsConfigEngine {}
+pub enum ErrorsConfigEngine {
+    #[error("the profile `{0}` is not defined in the configuration")]
+    UnknownProfile(String),
+    #[error("could not persist the profile\n-> {0}")]
+    PersistProfile(#[from] std::io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::show_settings;
+    use crate::config::{Origin, Setting};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_show_settings() {
+        let settings = vec![
+            Setting {
+                key: String::from("root_dir"),
+                value: String::from("\"/home/user/aliases\""),
+                origin: Origin::File(PathBuf::from("/home/user/.sam_rc.toml")),
+            },
+            Setting {
+                key: String::from("ttl"),
+                value: String::from("1800"),
+                origin: Origin::Default,
+            },
+        ];
+        assert_eq!(
+            show_settings(&settings, false),
+            vec!["root_dir = \"/home/user/aliases\"", "ttl = 1800"]
+        );
+        assert_eq!(
+            show_settings(&settings, true),
+            vec![
+                String::from("root_dir = \"/home/user/aliases\"  # /home/user/.sam_rc.toml"),
+                format!("{:<31}  # default", "ttl = 1800"),
+            ]
+        );
+    }
+}
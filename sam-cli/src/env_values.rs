@@ -0,0 +1,186 @@
+use sam_core::entities::processes::ShellCommand;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::process::{Command, Stdio};
+use thiserror::Error;
+
+/// the value of a variable of the `[env]` table of the configuration.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum EnvValue {
+    /// a literal value, `${OTHER}` is replaced by the value of the `OTHER` variable.
+    Literal(String),
+    /// the output of a command, run once when sam starts.
+    Command(EnvCommand),
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EnvCommand {
+    pub from_command: String,
+}
+
+impl Display for EnvValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EnvValue::Literal(value) => write!(f, "{:?}", value),
+            EnvValue::Command(command) => {
+                write!(f, "{{ from_command = {:?} }}", command.from_command)
+            }
+        }
+    }
+}
+
+/// computes the value of every variable. References are looked up in the definitions first
+/// then in the environment of the process.
+pub fn resolve_env(definitions: &HashMap<String, EnvValue>) -> Result<HashMap<String, String>> {
+    let mut resolver = Resolver {
+        definitions,
+        resolved: HashMap::new(),
+        visiting: vec![],
+    };
+    for key in definitions.keys() {
+        resolver.resolve(key)?;
+    }
+    Ok(resolver.resolved)
+}
+
+struct Resolver<'a> {
+    definitions: &'a HashMap<String, EnvValue>,
+    resolved: HashMap<String, String>,
+    // the variables being resolved, used to detect cyclic references.
+    visiting: Vec<String>,
+}
+
+impl Resolver<'_> {
+    fn resolve(&mut self, key: &str) -> Result<String> {
+        if let Some(value) = self.resolved.get(key) {
+            return Ok(value.clone());
+        }
+        let definition = match self.definitions.get(key) {
+            Some(definition) => definition,
+            None => {
+                return std::env::var(key).map_err(|_| {
+                    let referrer = self.visiting.last().cloned().unwrap_or_default();
+                    ErrorsEnvValues::UndefinedReference(key.to_string(), referrer)
+                })
+            }
+        };
+        if self.visiting.iter().any(|k| k == key) {
+            let mut cycle = self.visiting.clone();
+            cycle.push(key.to_string());
+            return Err(ErrorsEnvValues::CyclicReference(cycle.join(" -> ")));
+        }
+        self.visiting.push(key.to_string());
+        let value = match definition {
+            EnvValue::Literal(value) => self.expand(value)?,
+            EnvValue::Command(command) => {
+                let command = self.expand(&command.from_command)?;
+                run(key, &command)?
+            }
+        };
+        self.visiting.pop();
+        self.resolved.insert(key.to_string(), value.clone());
+        Ok(value)
+    }
+
+    fn expand(&mut self, value: &str) -> Result<String> {
+        let mut expanded = String::with_capacity(value.len());
+        let mut rest = value;
+        while let Some(start) = rest.find("${") {
+            let end = match rest[start..].find('}') {
+                Some(end) => start + end,
+                None => break,
+            };
+            expanded.push_str(&rest[..start]);
+            expanded.push_str(&self.resolve(&rest[start + 2..end])?);
+            rest = &rest[end + 1..];
+        }
+        expanded.push_str(rest);
+        Ok(expanded)
+    }
+}
+
+// stdin and stderr are inherited so that commands such as `pass` can prompt the user.
+fn run(key: &str, command: &str) -> Result<String> {
+    let mut to_run: Command = ShellCommand::new(command.to_string()).into();
+    let output = to_run
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| ErrorsEnvValues::CommandIO(key.to_string(), e))?;
+    if !output.status.success() {
+        return Err(ErrorsEnvValues::CommandFailed(
+            key.to_string(),
+            command.to_string(),
+            output.status.code().unwrap_or(-1),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .trim_end_matches(&['\n', '\r'][..])
+        .to_string())
+}
+
+type Result<T> = std::result::Result<T, ErrorsEnvValues>;
+
+#[derive(Debug, Error)]
+pub enum ErrorsEnvValues {
+    #[error("`{0}`, referenced by the env variable `{1}`, is not defined")]
+    UndefinedReference(String, String),
+    #[error("the env variables reference each other: {0}")]
+    CyclicReference(String),
+    #[error("could not run the command of the env variable `{0}`\n-> {1}")]
+    CommandIO(String, std::io::Error),
+    #[error("the command of the env variable `{0}`, `{1}`, exited with code {2}")]
+    CommandFailed(String, String, i32),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{resolve_env, EnvCommand, EnvValue, ErrorsEnvValues};
+    use std::collections::HashMap;
+
+    fn literal(value: &str) -> EnvValue {
+        EnvValue::Literal(value.to_string())
+    }
+
+    #[test]
+    fn test_resolve_env() {
+        std::env::set_var("SAM_TEST_ENV_USER", "alice");
+        let definitions: HashMap<String, EnvValue> = maplit::hashmap! {
+            String::from("URL") => literal("https://${HOST}/${SAM_TEST_ENV_USER}"),
+            String::from("HOST") => literal("example.org"),
+            String::from("TOKEN") => EnvValue::Command(EnvCommand {
+                from_command: String::from("echo token-${HOST}"),
+            }),
+        };
+        let env = resolve_env(&definitions).expect("the env should resolve");
+        assert_eq!(env["URL"], "https://example.org/alice");
+        assert_eq!(env["TOKEN"], "token-example.org");
+
+        let cyclic = maplit::hashmap! {
+            String::from("A") => literal("${B}"),
+            String::from("B") => literal("${A}"),
+        };
+        assert!(matches!(
+            resolve_env(&cyclic),
+            Err(ErrorsEnvValues::CyclicReference(_))
+        ));
+
+        let undefined = maplit::hashmap! { String::from("A") => literal("${SAM_TEST_ENV_UNSET}") };
+        assert!(matches!(
+            resolve_env(&undefined),
+            Err(ErrorsEnvValues::UndefinedReference(reference, referrer))
+                if reference == "SAM_TEST_ENV_UNSET" && referrer == "A"
+        ));
+
+        let failing = maplit::hashmap! {
+            String::from("A") => EnvValue::Command(EnvCommand { from_command: String::from("exit 3") }),
+        };
+        assert!(matches!(
+            resolve_env(&failing),
+            Err(ErrorsEnvValues::CommandFailed(_, _, 3))
+        ));
+    }
+}
@@ -1,15 +1,25 @@
 use crate::cache_engine::CacheEngine;
-use crate::config::AppSettings;
+use crate::completion_engine::CompletionEngine;
+use crate::config::{AppSettings, ErrorsSettings};
 use crate::config_engine::ConfigEngine;
-use crate::executors::{DryExecutor, ShellExecutor};
+use crate::executors::{DryExecutor, PrintExecutor, ShellExecutor};
+use crate::export_engine::ExportEngine;
+use crate::history_engine::HistoryEngine;
+use crate::import_engine::ImportEngine;
 use crate::logger::{SilentLogger, StdErrLogger};
 use crate::preview_engine::PreviewEngine;
+use crate::resolve_engine::ResolveEngine;
+use crate::stats_engine::StatsEngine;
+use sam_core::algorithms::VarsCollection;
 use sam_core::engines::{SamEngine, SamExecutor, SamLogger, VarsDefaultValuesSetter};
+use sam_core::entities::identifiers::Identifier;
+use sam_core::entities::vars::Var;
 use sam_persistence::repositories::{
     AliasesRepository, ErrorsAliasesRepository, ErrorsVarsRepository, VarsRepository,
 };
 use sam_persistence::{
-    AliasHistory, CacheError, ErrorAliasHistory, NoopVarsCache, RustBreakCache, VarsCache,
+    AliasHistory, CacheError, ErrorAliasHistory, HistoryContextDefaults, NoopVarsCache,
+    RustBreakCache, ShellHistory, VarsCache,
 };
 use sam_readers::read_aliases_from_path;
 use sam_readers::read_vars_repository;
@@ -22,6 +32,8 @@ use std::collections::HashMap;
 use std::rc::Rc;
 use thiserror::Error;
 
+pub const HISTORY_MAX_SIZE: usize = 1000;
+
 pub struct Environment {
     // TODO Todo remove user interface from the context
     pub ui_interface: UserInterface,
@@ -31,6 +43,7 @@ pub struct Environment {
     pub env_variables: HashMap<String, String>,
     pub config: AppSettings,
     pub history: RefCell<Box<dyn sam_core::engines::SamHistory>>,
+    pub shell_history: Option<ShellHistory>,
 }
 
 impl Environment {
@@ -39,8 +52,12 @@ impl Environment {
     ) -> SamEngine<UserInterface, AliasesRepository, VarsRepository, VarsRepository> {
         let executor: Rc<dyn SamExecutor> = if self.config.dry {
             Rc::new(DryExecutor {})
+        } else if self.config.print {
+            Rc::new(PrintExecutor {})
         } else {
-            Rc::new(ShellExecutor {})
+            Rc::new(ShellExecutor {
+                shell_history: self.shell_history,
+            })
         };
 
         SamEngine {
@@ -55,6 +72,58 @@ impl Environment {
         }
     }
 
+    pub fn history_engine(self) -> HistoryEngine {
+        HistoryEngine {
+            history_file: self.config.history_file().to_owned(),
+            aliases_files: self.config.aliases_files().collect(),
+            root_dirs: self.config.root_dirs().to_vec(),
+            sam_engine: self.sam_engine(),
+        }
+    }
+
+    pub fn resolve_engine(self) -> ResolveEngine {
+        ResolveEngine {
+            sam_engine: self.sam_engine(),
+            output: Box::new(std::io::stdout()),
+        }
+    }
+
+    pub fn stats_engine(self) -> StatsEngine {
+        StatsEngine {
+            history_file: self.config.history_file().to_owned(),
+            aliases: self.aliases,
+            output: Box::new(std::io::stdout()),
+        }
+    }
+
+    pub fn import_engine(self) -> ImportEngine {
+        ImportEngine {
+            root_dirs: self.config.root_dirs().to_vec(),
+            aliases: self.aliases,
+            vars: self.vars,
+            output: Box::new(std::io::stdout()),
+            dry: self.config.dry,
+        }
+    }
+
+    pub fn export_engine(self) -> ExportEngine {
+        ExportEngine {
+            aliases: self.aliases,
+            vars: self.vars,
+            output: Box::new(std::io::stdout()),
+        }
+    }
+
+    pub fn completion_engine(self) -> CompletionEngine {
+        CompletionEngine {
+            cache_dir: self.config.cache_dir().to_owned(),
+            ttl: self.config.ttl(),
+            aliases: self.aliases,
+            vars: self.vars,
+            output: Box::new(std::io::stdout()),
+        }
+    }
+
     pub fn preview_engine(self) -> PreviewEngine {
         PreviewEngine {
             aliases: self.aliases,
@@ -68,6 +137,10 @@ impl Environment {
         CacheEngine {
             cache_dir: self.config.cache_dir().to_owned(),
             ttl: self.config.ttl(),
+            ui_interface: self.ui_interface,
+            aliases: self.aliases,
+            vars: self.vars,
+            env_variables: self.env_variables,
         }
     }
 
@@ -76,6 +149,10 @@ impl Environment {
             aliases: self.aliases,
             vars: self.vars,
             env_variables: self.env_variables,
+            settings: self.config.effective_settings(),
+            profiles: self.config.profiles().cloned().collect(),
+            profile: self.config.profile().map(String::from),
+            profile_file: self.config.profile_file().to_owned(),
         }
     }
 }
@@ -86,12 +163,17 @@ pub fn from_settings(config: AppSettings) -> Result<Environment> {
     } else {
         Box::new(NoopVarsCache {})
     };
-    let history: RefCell<Box<dyn sam_core::engines::SamHistory>> = RefCell::new(Box::new(
-        AliasHistory::new(config.history_file(), Some(1000))?,
-    ));
+    let alias_history = AliasHistory::new(config.history_file(), Some(HISTORY_MAX_SIZE))?;
+    // the history records canonical paths.
+    let cwd = std::env::current_dir()?;
+    let cwd = cwd.canonicalize().unwrap_or(cwd);
+    let suggestions = HistoryContextDefaults::from_history_file(config.history_file(), cwd);
+    let history: RefCell<Box<dyn sam_core::engines::SamHistory>> =
+        RefCell::new(Box::new(alias_history));
+
+    let shell_history = config.shell_history()?;
 
     let logger = logger_instance(config.silent);
-    let ui_interface = UserInterface::new(config.variables(), cache)?;
 
     let mut aliases_vec = vec![];
     for f in config.aliases_files() {
@@ -106,6 +188,19 @@ pub fn from_settings(config: AppSettings) -> Result<Environment> {
     vars.set_defaults(&config.defaults);
     vars.ensure_no_missing_dependency()?;
 
+    let secrets: Vec<Identifier> = vars
+        .vars_iter()
+        .map(Var::name)
+        .filter(|id| vars.is_secret(id))
+        .collect();
+    // without a terminal there is no one to answer the prompts.
+    let non_interactive = config.non_interactive || !termion::is_tty(&std::io::stdin());
+    let ui_interface = UserInterface::new(config.variables(), cache)?
+        .with_suggestions(Box::new(suggestions))
+        .with_non_interactive(non_interactive)
+        .with_secrets(secrets)
+        .with_preview_args(preview_args(&config));
+
     Ok(Environment {
         ui_interface,
         aliases,
@@ -114,9 +209,28 @@ pub fn from_settings(config: AppSettings) -> Result<Environment> {
         env_variables: config.variables(),
         config,
         history,
+        shell_history,
     })
 }
 
+// the previews are rendered by another sam process, it must read the same configuration.
+fn preview_args(config: &AppSettings) -> Vec<String> {
+    let mut args = vec![];
+    if let Some(config_file) = config.config_file() {
+        let config_file = std::env::current_dir()
+            .map(|cwd| cwd.join(config_file))
+            .unwrap_or_else(|_| config_file.to_owned());
+        args.extend([
+            String::from("--config"),
+            config_file.to_string_lossy().into(),
+        ]);
+    }
+    if let Some(profile) = config.profile() {
+        args.extend([String::from("--profile"), profile.to_string()]);
+    }
+    args
+}
+
 fn logger_instance(silent: bool) -> Rc<dyn SamLogger> {
     if !silent {
         Rc::new(StdErrLogger)
@@ -144,4 +258,8 @@ pub enum ErrorEnvironment {
     ErrAliasHistory(#[from] ErrorAliasHistory),
     #[error("could not open the vars cache because\n-> {0}")]
     CacheError(#[from] CacheError),
+    #[error("invalid settings\n-> {0}")]
+    Settings(#[from] ErrorsSettings),
+    #[error("could not figure out the current directory because\n-> {0}")]
+    CurrentDir(#[from] std::io::Error),
 }
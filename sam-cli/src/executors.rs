@@ -1,12 +1,17 @@
 use std::collections::HashMap;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 use sam_core::engines::{ErrorSamEngine, SamExecutor};
 use sam_core::entities::{
     processes::ShellCommand,
     {aliases::ResolvedAlias, commands::Command},
 };
+use sam_persistence::ShellHistory;
 
-pub struct ShellExecutor {}
+pub struct ShellExecutor {
+    /// when set, every executed command is also appended to the user's shell history.
+    pub shell_history: Option<ShellHistory>,
+}
 
 impl SamExecutor for ShellExecutor {
     fn execute_resolved_alias(
@@ -16,11 +21,41 @@ impl SamExecutor for ShellExecutor {
     ) -> Result<i32, ErrorSamEngine> {
         let mut command: std::process::Command = ShellCommand::new(alias.command()).into();
         command.envs(env_variables);
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let start = Instant::now();
         let exit_status = command.status()?;
+        if let Some(shell_history) = &self.shell_history {
+            // the command already ran, failing to record it should not fail the run.
+            if let Err(e) =
+                shell_history.append(&alias.redacted_command(), started_at, start.elapsed())
+            {
+                eprintln!("{}", e);
+            }
+        }
         exit_status.code().ok_or(ErrorSamEngine::ExitCode)
     }
 }
 
+/// PrintExecutor writes the command to stdout, for the shell to insert it in the prompt.
+pub struct PrintExecutor {}
+impl SamExecutor for PrintExecutor {
+    fn execute_resolved_alias(
+        &self,
+        alias: &ResolvedAlias,
+        _env_variables: &HashMap<String, String>,
+    ) -> Result<i32, ErrorSamEngine> {
+        println!("{}", alias.command());
+        Ok(0)
+    }
+
+    fn prints_command(&self) -> bool {
+        true
+    }
+}
+
 pub struct DryExecutor {}
 impl SamExecutor for DryExecutor {
     fn execute_resolved_alias(
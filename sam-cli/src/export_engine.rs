@@ -0,0 +1,216 @@
+use sam_core::algorithms::{execution_sequence_for_dependencies, VarsCollection};
+use sam_core::entities::aliases::Alias;
+use sam_core::entities::identifiers::Identifier;
+use sam_persistence::repositories::{AliasesRepository, VarsRepository};
+use sam_persistence::ShellKind;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use thiserror::Error;
+
+pub struct ExportEngine {
+    pub aliases: AliasesRepository,
+    pub vars: VarsRepository,
+    pub output: Box<dyn Write>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportCommand {
+    Shell {
+        shell: ShellKind,
+        file: Option<PathBuf>,
+    },
+}
+
+// what an alias is exported as.
+enum Exported {
+    // an alias without vars, a plain shell alias.
+    Alias(Alias),
+    // a templated alias, a function calling sam with its positional arguments as choices.
+    Function(Alias, Vec<Identifier>),
+    // an alias whose vars could not be figured out.
+    Skipped(Alias, String),
+}
+
+impl ExportEngine {
+    pub fn run(self, cmd: ExportCommand) -> Result<i32> {
+        match cmd {
+            ExportCommand::Shell { shell, file } => self.export_shell(shell, file),
+        }
+    }
+
+    fn export_shell(mut self, shell: ShellKind, file: Option<PathBuf>) -> Result<i32> {
+        let mut aliases = self.aliases.aliases();
+        aliases.sort_by(|a, b| a.full_name().cmp(&b.full_name()));
+        let exported: Vec<Exported> = aliases.into_iter().map(|a| self.exported(a)).collect();
+
+        match file {
+            Some(path) => {
+                let mut out = File::create(&path).map_err(|e| ErrorExportEngine::File(path, e))?;
+                write_script(&mut out, shell, &exported)?;
+            }
+            None => write_script(&mut self.output, shell, &exported)?,
+        }
+        Ok(0)
+    }
+
+    fn exported(&self, alias: Alias) -> Exported {
+        match execution_sequence_for_dependencies(&self.vars, &alias) {
+            Ok(seq) if seq.identifiers().is_empty() => Exported::Alias(alias),
+            Ok(seq) => {
+                // secret vars are always prompted for, they don't end up in the shell history.
+                let args = seq
+                    .identifiers()
+                    .into_iter()
+                    .filter(|id| !self.vars.is_secret(id))
+                    .collect();
+                Exported::Function(alias, args)
+            }
+            Err(e) => Exported::Skipped(alias, e.to_string()),
+        }
+    }
+}
+
+fn write_script(output: &mut dyn Write, shell: ShellKind, exported: &[Exported]) -> Result<()> {
+    writeln!(
+        output,
+        "# aliases exported from sam, source this file from your shell"
+    )?;
+    for entry in exported {
+        writeln!(output)?;
+        match entry {
+            Exported::Alias(alias) => write_alias(output, shell, alias)?,
+            Exported::Function(alias, args) => write_function(output, shell, alias, args)?,
+            Exported::Skipped(alias, reason) => {
+                let reason = reason.lines().collect::<Vec<&str>>().join(" ");
+                writeln!(output, "# skipped {}: {}", alias.full_name(), reason)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_alias(output: &mut dyn Write, shell: ShellKind, alias: &Alias) -> std::io::Result<()> {
+    match shell {
+        ShellKind::Bash | ShellKind::Zsh => write!(output, "{}", alias),
+        ShellKind::Fish => {
+            writeln!(output, "# {}", one_line(alias.desc()))?;
+            writeln!(
+                output,
+                "alias {} {}",
+                alias.full_name(),
+                fish_quote(alias.alias())
+            )
+        }
+    }
+}
+
+fn write_function(
+    output: &mut dyn Write,
+    shell: ShellKind,
+    alias: &Alias,
+    args: &[Identifier],
+) -> std::io::Result<()> {
+    let name = alias.full_name();
+    let usage: Vec<String> = args.iter().map(|id| format!("[{}]", id.name())).collect();
+    writeln!(output, "# {}", one_line(alias.desc()))?;
+    writeln!(output, "# usage: {} {}", name, usage.join(" "))?;
+    // an empty argument leaves the var to be prompted for.
+    match shell {
+        ShellKind::Bash | ShellKind::Zsh => {
+            writeln!(output, "{}() {{", name)?;
+            writeln!(output, "    local choices=()")?;
+            for (idx, id) in args.iter().enumerate() {
+                writeln!(
+                    output,
+                    "    [ -n \"${{{n}}}\" ] && choices+=(-c \"{}=${{{n}}}\")",
+                    id,
+                    n = idx + 1
+                )?;
+            }
+            writeln!(output, "    sam alias {} \"${{choices[@]}}\"", name)?;
+            writeln!(output, "}}")
+        }
+        ShellKind::Fish => {
+            writeln!(output, "function {}", name)?;
+            writeln!(output, "    set -l choices")?;
+            for (idx, id) in args.iter().enumerate() {
+                writeln!(output, "    if test -n \"$argv[{}]\"", idx + 1)?;
+                writeln!(
+                    output,
+                    "        set -a choices -c \"{}=$argv[{}]\"",
+                    id,
+                    idx + 1
+                )?;
+                writeln!(output, "    end")?;
+            }
+            writeln!(output, "    sam alias {} $choices", name)?;
+            writeln!(output, "end")
+        }
+    }
+}
+
+fn one_line(text: &str) -> String {
+    text.lines().collect::<Vec<&str>>().join(" ")
+}
+
+// fish only interprets `\\` and `\'` between single quotes.
+fn fish_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+type Result<T> = std::result::Result<T, ErrorExportEngine>;
+
+#[derive(Debug, Error)]
+pub enum ErrorExportEngine {
+    #[error("could not write the export\n-> {0}")]
+    Output(#[from] std::io::Error),
+    #[error("could not create the file {0}\n-> {1}")]
+    File(PathBuf, std::io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_script, Exported};
+    use sam_core::entities::aliases::Alias;
+    use sam_core::entities::identifiers::Identifier;
+    use sam_core::entities::namespaces::NamespaceUpdater;
+    use sam_persistence::ShellKind;
+
+    fn exported() -> Vec<Exported> {
+        let mut list = Alias::new("list", "lists it's", "ls -l | grep 'x'");
+        NamespaceUpdater::update(&mut list, "ns");
+        let mut show = Alias::new("show", "shows a file", "cat {{ dir }}/{{ file }}");
+        NamespaceUpdater::update(&mut show, "ns");
+        let args = vec![
+            Identifier::with_namespace("dir", Some("ns")),
+            Identifier::with_namespace("file", Some("ns")),
+        ];
+        vec![Exported::Alias(list), Exported::Function(show, args)]
+    }
+
+    fn script(shell: ShellKind) -> String {
+        let mut output = vec![];
+        write_script(&mut output, shell, &exported()).expect("should write the script");
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_export_bash() {
+        let script = script(ShellKind::Bash);
+        assert!(script.contains("alias ns::list='ls -l | grep '\\''x'\\''' # lists it's\n"));
+        assert!(script.contains(
+            "ns::show() {\n    local choices=()\n    [ -n \"${1}\" ] && choices+=(-c \"ns::dir=${1}\")\n    [ -n \"${2}\" ] && choices+=(-c \"ns::file=${2}\")\n    sam alias ns::show \"${choices[@]}\"\n}\n"
+        ));
+    }
+
+    #[test]
+    fn test_export_fish() {
+        let script = script(ShellKind::Fish);
+        assert!(script.contains("alias ns::list 'ls -l | grep \\'x\\''\n"));
+        assert!(script.contains(
+            "function ns::show\n    set -l choices\n    if test -n \"$argv[1]\"\n        set -a choices -c \"ns::dir=$argv[1]\"\n    end\n"
+        ));
+        assert!(script.contains("    sam alias ns::show $choices\nend\n"));
+    }
+}
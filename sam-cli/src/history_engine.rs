@@ -0,0 +1,560 @@
+use crate::environment::HISTORY_MAX_SIZE;
+use sam_core::engines::{AliasCollection, ErrorSamEngine, SamCommand, SamEngine};
+use sam_core::entities::aliases::Alias;
+use sam_core::entities::choices::Choice;
+use sam_core::entities::history::{HistoryEntry, HistoryFilter};
+use sam_core::entities::identifiers::Identifier;
+use sam_persistence::repositories::{AliasesRepository, VarsRepository};
+use sam_persistence::{AliasHistory, ErrorAliasHistory, ErrorSeqStateInteractor};
+use sam_readers::{append_alias_to_path, is_namespace_file, ErrorsAliasWrite};
+use sam_tui::{ErrorsUI, UserInterface};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::PathBuf;
+use thiserror::Error;
+
+const ACTION_RUN: &str = "run";
+const ACTION_EDIT: &str = "edit then run";
+const ACTION_DELETE: &str = "delete";
+
+pub struct HistoryEngine {
+    pub history_file: PathBuf,
+    pub aliases_files: Vec<PathBuf>,
+    pub root_dirs: Vec<PathBuf>,
+    pub sam_engine: SamEngine<UserInterface, AliasesRepository, VarsRepository, VarsRepository>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HistoryCommand {
+    Display {
+        filter: HistoryFilter,
+        limit: usize,
+        json: bool,
+    },
+    Pick {
+        filter: HistoryFilter,
+    },
+    Export {
+        filter: HistoryFilter,
+        format: ExportFormat,
+    },
+    Save {
+        index: Option<usize>,
+        name: Identifier,
+        desc: Option<String>,
+        keep_defaults: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Shell,
+    Markdown,
+    Json,
+}
+
+impl HistoryEngine {
+    pub fn run(self, cmd: HistoryCommand) -> Result<i32> {
+        match cmd {
+            HistoryCommand::Display {
+                filter,
+                limit,
+                json,
+            } => self.display(&filter, limit, json),
+            HistoryCommand::Pick { filter } => self.pick(&filter),
+            HistoryCommand::Export { filter, format } => self.export(&filter, format),
+            HistoryCommand::Save {
+                index,
+                name,
+                desc,
+                keep_defaults,
+            } => self.save(index, name, desc, keep_defaults),
+        }
+    }
+
+    fn display(self, filter: &HistoryFilter, limit: usize, json: bool) -> Result<i32> {
+        let mut entries = self.filtered_entries(filter)?;
+        let skip = entries.len().saturating_sub(limit);
+        let entries: Vec<(usize, HistoryEntry)> = entries.drain(skip..).collect();
+
+        if json {
+            let entries: Vec<JsonEntry<'_>> =
+                entries.iter().map(|(_, e)| JsonEntry::from(e)).collect();
+            println!("{}", serde_json::to_string_pretty(&entries)?);
+            return Ok(0);
+        }
+        for (position, entry) in entries {
+            println!("\n=============\n");
+            println!(
+                "{}{}Index:{} {}",
+                termion::color::Fg(termion::color::LightCyan),
+                termion::style::Bold,
+                termion::style::Reset,
+                position,
+            );
+            print!("{}", entry);
+            print!("\n=============\n");
+        }
+        Ok(0)
+    }
+
+    fn export(self, filter: &HistoryFilter, format: ExportFormat) -> Result<i32> {
+        let entries: Vec<HistoryEntry> = self
+            .filtered_entries(filter)?
+            .into_iter()
+            .map(|(_, e)| e)
+            .collect();
+        let stdout = std::io::stdout();
+        let mut output = stdout.lock();
+        match format {
+            ExportFormat::Shell => write_shell_script(&mut output, &entries)?,
+            ExportFormat::Markdown => write_runbook(&mut output, &entries)?,
+            ExportFormat::Json => {
+                let entries: Vec<JsonEntry<'_>> = entries.iter().map(JsonEntry::from).collect();
+                serde_json::to_writer_pretty(&mut output, &entries)?;
+                writeln!(output)?;
+            }
+        }
+        Ok(0)
+    }
+
+    fn pick(mut self, filter: &HistoryFilter) -> Result<i32> {
+        let history = self.open()?;
+        let command = {
+            let interactor = history.interactor(&self.sam_engine.resolver);
+            let (position, entry) = match interactor.select_entry_where(|e| filter.matches(e))? {
+                Some(selected) => selected,
+                None => return Ok(0),
+            };
+            let action = self.sam_engine.resolver.select_action(
+                &[ACTION_RUN, ACTION_EDIT, ACTION_DELETE],
+                "What should be done with this entry > ",
+            )?;
+            match action {
+                Some(ACTION_RUN) => SamCommand::ExecuteResolvedAlias { alias: entry.alias },
+                Some(ACTION_EDIT) => SamCommand::ModifyThenExecuteAlias { alias: entry.alias },
+                Some(ACTION_DELETE) => {
+                    interactor.delete_at(position)?;
+                    println!("deleted history entry for {}", entry.alias.name());
+                    return Ok(0);
+                }
+                _ => return Ok(0),
+            }
+        };
+        Ok(self.sam_engine.run(command)?)
+    }
+
+    fn save(
+        self,
+        index: Option<usize>,
+        name: Identifier,
+        desc: Option<String>,
+        keep_defaults: bool,
+    ) -> Result<i32> {
+        let mut entries = self.open()?.entries()?;
+        let entry = match index {
+            Some(idx) if idx < entries.len() => entries.swap_remove(idx),
+            None if !entries.is_empty() => entries.swap_remove(entries.len() - 1),
+            _ => return Err(ErrorHistoryEngine::MissingEntry(index)),
+        };
+        if self.sam_engine.aliases.get(&name).is_ok() {
+            return Err(ErrorHistoryEngine::AliasAlreadyExists(name));
+        }
+        let namespace = name
+            .namespace
+            .clone()
+            .ok_or_else(|| ErrorHistoryEngine::MissingNamespace(name.clone()))?;
+        let desc = desc.unwrap_or_else(|| entry.alias.desc().to_string());
+        let alias = saved_alias(&entry, &name, &desc, keep_defaults);
+
+        let target = self.aliases_file_for(&namespace)?;
+        append_alias_to_path(&target, &alias)?;
+        println!("saved alias {} into {}", name, target.as_path().display());
+        Ok(0)
+    }
+
+    // the aliases file of an existing namespace, or a new one in the first root directory.
+    // Single-file namespaces are left alone, the saved alias goes in the namespace directory.
+    fn aliases_file_for(&self, namespace: &str) -> Result<PathBuf> {
+        let existing = self.aliases_files.iter().find(|f| {
+            !is_namespace_file(f)
+                && f.parent()
+                    .and_then(|p| p.file_name())
+                    .map(|n| n == namespace)
+                    .unwrap_or(false)
+        });
+        match (existing, self.root_dirs.first()) {
+            (Some(f), _) => Ok(f.clone()),
+            (None, Some(root)) => Ok(root.join(namespace).join("aliases.yaml")),
+            (None, None) => Err(ErrorHistoryEngine::NoRootDir),
+        }
+    }
+
+    fn filtered_entries(&self, filter: &HistoryFilter) -> Result<Vec<(usize, HistoryEntry)>> {
+        Ok(self
+            .open()?
+            .entries()?
+            .into_iter()
+            .enumerate()
+            .filter(|(_, e)| filter.matches(e))
+            .collect())
+    }
+
+    fn open(&self) -> Result<AliasHistory> {
+        Ok(AliasHistory::new(
+            &self.history_file,
+            Some(HISTORY_MAX_SIZE),
+        )?)
+    }
+}
+
+// the alias saved from a history entry, either the template with the choices as defaults
+// or the command as it ran. The choices of secret vars are redacted in the history,
+// these vars are kept in the command and prompted for.
+fn saved_alias(entry: &HistoryEntry, name: &Identifier, desc: &str, keep_defaults: bool) -> Alias {
+    // explicit namespaces keep the template valid whatever namespace it is saved into.
+    let template = Alias::from(entry.alias.clone());
+    if keep_defaults {
+        let defaults = entry
+            .alias
+            .choices()
+            .iter()
+            .filter(|(id, _)| !entry.alias.is_secret(id))
+            .map(|(id, choice)| (id.to_string(), choice.value().to_string()))
+            .collect();
+        Alias::new(name.name(), desc, template.sanitized_alias().as_str()).with_defaults(defaults)
+    } else if entry.alias.has_secrets() {
+        let choices: HashMap<Identifier, Choice> = entry
+            .alias
+            .choices()
+            .iter()
+            .filter(|(id, _)| !entry.alias.is_secret(id))
+            .map(|(id, choice)| (id.clone(), choice.clone()))
+            .collect();
+        let command = template.with_partial_choices(&choices).sanitized_alias();
+        Alias::new(name.name(), desc, command.as_str())
+    } else {
+        Alias::new(name.name(), desc, entry.alias.resolved_alias())
+    }
+}
+
+// the command of an entry that can be run again. The choices of secret vars are redacted in
+// the history, they are read from the `SAM_<NS>_<VAR>` environment variables instead.
+// Returns the names of those variables along with the command.
+fn replayable_command(entry: &HistoryEntry) -> (Vec<String>, String) {
+    if !entry.alias.has_secrets() {
+        return (vec![], entry.alias.resolved_alias().to_string());
+    }
+    let mut env_names = vec![];
+    let choices: HashMap<Identifier, Choice> = entry
+        .alias
+        .choices()
+        .iter()
+        .map(|(id, choice)| match entry.alias.is_secret(id) {
+            true => {
+                env_names.push(id.env_name());
+                let reference = format!("\"${{{}}}\"", id.env_name());
+                (id.clone(), Choice::from_value(reference))
+            }
+            false => (id.clone(), choice.clone()),
+        })
+        .collect();
+    env_names.sort();
+    let template = Alias::from(entry.alias.clone());
+    let command = template.with_partial_choices(&choices).alias().to_string();
+    (env_names, command)
+}
+
+// sorted choices, to keep the exports stable.
+fn sorted_choices(entry: &HistoryEntry) -> Vec<(String, &str)> {
+    let mut choices: Vec<(String, &str)> = entry
+        .alias
+        .choices()
+        .iter()
+        .map(|(id, choice)| (id.to_string(), choice.value()))
+        .collect();
+    choices.sort();
+    choices
+}
+
+fn exit_code_description(entry: &HistoryEntry) -> String {
+    match entry.exit_code {
+        _ if entry.printed => String::from("printed, not executed"),
+        Some(code) => code.to_string(),
+        None => String::from("unknown"),
+    }
+}
+
+fn write_shell_script(output: &mut dyn Write, entries: &[HistoryEntry]) -> std::io::Result<()> {
+    writeln!(output, "#!/usr/bin/env bash")?;
+    writeln!(output, "# commands exported from the sam history")?;
+    for entry in entries {
+        writeln!(output)?;
+        writeln!(
+            output,
+            "# alias: {} - {}",
+            entry.alias.name(),
+            comment(entry.alias.desc())
+        )?;
+        if let Some(when) = entry.started_at_local() {
+            writeln!(output, "# started at: {}", when)?;
+        }
+        writeln!(output, "# exit code: {}", exit_code_description(entry))?;
+        let template = comment(entry.alias.original_alias());
+        writeln!(output, "# template: {}", template)?;
+        for (var, value) in sorted_choices(entry) {
+            writeln!(output, "# choice: {} = {}", var, comment(value))?;
+        }
+        let (env_names, command) = replayable_command(entry);
+        // the script stops before running a command that is missing a secret.
+        for env_name in env_names {
+            writeln!(output, ": \"${{{0}:?{0} must hold a secret}}\"", env_name)?;
+        }
+        writeln!(output, "cd {}", shellwords::escape(&entry.pwd))?;
+        writeln!(output, "{}", command)?;
+    }
+    Ok(())
+}
+
+// comment continues the comment of the current script line on each line of `text`.
+fn comment(text: &str) -> String {
+    text.lines().collect::<Vec<&str>>().join("\n# ")
+}
+
+fn write_runbook(output: &mut dyn Write, entries: &[HistoryEntry]) -> std::io::Result<()> {
+    writeln!(output, "# Runbook")?;
+    for (idx, entry) in entries.iter().enumerate() {
+        writeln!(output)?;
+        writeln!(output, "## {}. `{}`", idx + 1, entry.alias.name())?;
+        writeln!(output)?;
+        if !entry.alias.desc().is_empty() {
+            writeln!(output, "{}", entry.alias.desc())?;
+            writeln!(output)?;
+        }
+        if let Some(when) = entry.started_at_local() {
+            writeln!(output, "- **Started at:** {}", when)?;
+        }
+        writeln!(output, "- **Directory:** `{}`", entry.pwd)?;
+        writeln!(output, "- **Exit code:** {}", exit_code_description(entry))?;
+        writeln!(output, "- **Template:** `{}`", entry.alias.original_alias())?;
+        let choices = sorted_choices(entry);
+        if !choices.is_empty() {
+            writeln!(output, "- **Choices:**")?;
+            for (var, value) in choices {
+                writeln!(output, "  - `{}`: `{}`", var, value)?;
+            }
+        }
+        let (env_names, command) = replayable_command(entry);
+        if !env_names.is_empty() {
+            let env_names: Vec<String> = env_names.iter().map(|e| format!("`{}`", e)).collect();
+            writeln!(output, "- **Secrets:** {}", env_names.join(", "))?;
+        }
+        writeln!(output)?;
+        writeln!(output, "```sh")?;
+        writeln!(output, "{}", command)?;
+        writeln!(output, "```")?;
+    }
+    Ok(())
+}
+
+// JsonEntry is the json representation of a history entry,
+// the choices are keyed by the full var identifier.
+#[derive(Serialize)]
+struct JsonEntry<'a> {
+    alias: String,
+    description: &'a str,
+    original_command: &'a str,
+    command: &'a str,
+    choices: BTreeMap<String, &'a str>,
+    pwd: &'a str,
+    started_at: Option<u64>,
+    duration_ms: Option<u64>,
+    exit_code: Option<i32>,
+    printed: bool,
+    hostname: Option<&'a str>,
+    user: Option<&'a str>,
+    version: Option<&'a str>,
+}
+
+impl<'a> From<&'a HistoryEntry> for JsonEntry<'a> {
+    fn from(entry: &'a HistoryEntry) -> Self {
+        JsonEntry {
+            alias: entry.alias.name().to_string(),
+            description: entry.alias.desc(),
+            original_command: entry.alias.original_alias(),
+            command: entry.alias.resolved_alias(),
+            choices: entry
+                .alias
+                .choices()
+                .iter()
+                .map(|(id, choice)| (id.to_string(), choice.value()))
+                .collect(),
+            pwd: entry.pwd.as_str(),
+            started_at: entry.started_at,
+            duration_ms: entry.duration_ms,
+            exit_code: entry.exit_code,
+            printed: entry.printed,
+            hostname: entry.hostname.as_deref(),
+            user: entry.user.as_deref(),
+            version: entry.version.as_deref(),
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, ErrorHistoryEngine>;
+
+#[derive(Debug, Error)]
+pub enum ErrorHistoryEngine {
+    #[error("could not read the history\n -> {0}")]
+    History(#[from] ErrorAliasHistory),
+    #[error("could not select a history entry\n -> {0}")]
+    Interactor(#[from] ErrorSeqStateInteractor),
+    #[error("could not select what to do with the history entry\n -> {0}")]
+    UI(#[from] ErrorsUI),
+    #[error("could not serialize the history\n -> {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("could not write the history\n -> {0}")]
+    Output(#[from] std::io::Error),
+    #[error("no history entry found at index {0:?}")]
+    MissingEntry(Option<usize>),
+    #[error("the alias {0} already exists")]
+    AliasAlreadyExists(Identifier),
+    #[error("the alias {0} should have a namespace, example 'ns::name'")]
+    MissingNamespace(Identifier),
+    #[error("no root directory is configured to save the alias into")]
+    NoRootDir,
+    #[error("could not save the alias\n -> {0}")]
+    AliasWrite(#[from] ErrorsAliasWrite),
+    #[error("{0}")]
+    SamEngine(#[from] ErrorSamEngine),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{saved_alias, write_runbook, write_shell_script};
+    use sam_core::entities::aliases::ResolvedAlias;
+    use sam_core::entities::choices::Choice;
+    use sam_core::entities::history::HistoryEntry;
+    use sam_core::entities::identifiers::Identifier;
+
+    fn entries() -> Vec<HistoryEntry> {
+        let alias = ResolvedAlias::new(
+            Identifier::with_namespace("pods", Some("k8s")),
+            String::from("list pods"),
+            String::from("kubectl --context {{cluster}} get pods"),
+            String::from("kubectl --context prod get pods"),
+            maplit::hashmap! {
+                Identifier::with_namespace("cluster", Some("k8s")) => Choice::from_value("prod"),
+            },
+        );
+        vec![HistoryEntry::new(alias, "/home/user/my service")]
+    }
+
+    #[test]
+    fn test_export_shell_script() {
+        let mut output = vec![];
+        write_shell_script(&mut output, &entries()).expect("should write the script");
+        let script = String::from_utf8(output).unwrap();
+        assert!(script.starts_with("#!/usr/bin/env bash\n"));
+        assert!(script.contains("# alias: k8s::pods - list pods\n"));
+        assert!(script.contains("# choice: k8s::cluster = prod\n"));
+        assert!(script.contains("cd /home/user/my\\ service\nkubectl --context prod get pods\n"));
+    }
+
+    #[test]
+    fn test_export_shell_script_comments_multi_line_texts() {
+        let alias = ResolvedAlias::new(
+            Identifier::with_namespace("clean", Some("fs")),
+            String::from("removes files\nrm -rf /"),
+            String::from("find {{dir}} -delete"),
+            String::from("find /tmp -delete"),
+            maplit::hashmap! {
+                Identifier::with_namespace("dir", Some("fs")) => Choice::from_value("/tmp\nreboot"),
+            },
+        );
+        let mut output = vec![];
+        write_shell_script(&mut output, &[HistoryEntry::new(alias, "/")])
+            .expect("should write the script");
+        let script = String::from_utf8(output).unwrap();
+        assert!(script.contains("# alias: fs::clean - removes files\n# rm -rf /\n"));
+        assert!(script.contains("# choice: fs::dir = /tmp\n# reboot\n"));
+        let commands: Vec<&str> = script.lines().filter(|l| !l.starts_with('#')).collect();
+        assert_eq!(commands, vec!["", "cd /", "find /tmp -delete"]);
+    }
+
+    #[test]
+    fn test_saved_alias_keeps_secret_vars() {
+        let token = Identifier::with_namespace("token", Some("ns"));
+        let alias = ResolvedAlias::new(
+            Identifier::with_namespace("login", Some("ns")),
+            String::from("logs in"),
+            String::from("login --user {{user}} --token {{ token }}"),
+            String::from("login --user me --token s3cr3t"),
+            maplit::hashmap! {
+                Identifier::with_namespace("user", Some("ns")) => Choice::from_value("me"),
+                token.clone() => Choice::from_value("s3cr3t"),
+            },
+        )
+        .with_secrets(vec![token]);
+        // the history only ever stores the redacted alias.
+        let entry = HistoryEntry::new(alias.redacted(), "/tmp");
+        let name = Identifier::with_namespace("my_login", Some("other"));
+
+        let saved = saved_alias(&entry, &name, "desc", false);
+        assert_eq!(saved.alias(), "login --user me --token {{ ns::token }}");
+
+        let saved = saved_alias(&entry, &name, "desc", true);
+        assert_eq!(
+            saved.alias(),
+            "login --user {{ ns::user }} --token {{ ns::token }}"
+        );
+        assert_eq!(saved.raw_defaults().len(), 1);
+        assert_eq!(
+            saved.raw_defaults().get("ns::user"),
+            Some(&String::from("me"))
+        );
+    }
+
+    #[test]
+    fn test_export_runbook() {
+        let mut output = vec![];
+        write_runbook(&mut output, &entries()).expect("should write the runbook");
+        let runbook = String::from_utf8(output).unwrap();
+        assert!(runbook.contains("## 1. `k8s::pods`\n"));
+        assert!(runbook.contains("  - `k8s::cluster`: `prod`\n"));
+        assert!(runbook.contains("```sh\nkubectl --context prod get pods\n```\n"));
+    }
+
+    #[test]
+    fn test_export_secrets() {
+        let token = Identifier::with_namespace("token", Some("ns"));
+        let alias = ResolvedAlias::new(
+            Identifier::with_namespace("login", Some("ns")),
+            String::from("logs in"),
+            String::from("login --user {{user}} --token {{token}}"),
+            String::from("login --user me --token s3cr3t"),
+            maplit::hashmap! {
+                Identifier::with_namespace("user", Some("ns")) => Choice::from_value("me"),
+                token.clone() => Choice::from_value("s3cr3t"),
+            },
+        )
+        .with_secrets(vec![token]);
+        let entries = vec![HistoryEntry::new(alias.redacted(), "/tmp")];
+
+        let mut output = vec![];
+        write_shell_script(&mut output, &entries).expect("should write the script");
+        let script = String::from_utf8(output).unwrap();
+        assert!(script.contains(
+            ": \"${SAM_NS_TOKEN:?SAM_NS_TOKEN must hold a secret}\"\n\
+             cd /tmp\n\
+             login --user me --token \"${SAM_NS_TOKEN}\"\n"
+        ));
+
+        let mut output = vec![];
+        write_runbook(&mut output, &entries).expect("should write the runbook");
+        let runbook = String::from_utf8(output).unwrap();
+        assert!(runbook.contains("- **Secrets:** `SAM_NS_TOKEN`\n"));
+        assert!(runbook.contains("```sh\nlogin --user me --token \"${SAM_NS_TOKEN}\"\n```\n"));
+    }
+}
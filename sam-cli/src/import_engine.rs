@@ -0,0 +1,217 @@
+use sam_core::algorithms::VarsCollection;
+use sam_core::engines::AliasCollection;
+use sam_core::entities::namespaces::NamespaceUpdater;
+use sam_persistence::repositories::{AliasesRepository, VarsRepository};
+use sam_readers::{
+    aliases_to_yaml, append_to_path, import_navi, import_shell, vars_to_yaml, ErrorsSerde,
+    Imported, Skipped,
+};
+use sam_utils::fsutils::{walk_dir, ErrorsFS};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+pub struct ImportEngine {
+    pub root_dirs: Vec<PathBuf>,
+    pub aliases: AliasesRepository,
+    pub vars: VarsRepository,
+    pub output: Box<dyn Write>,
+    pub dry: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportCommand {
+    Shell {
+        file: PathBuf,
+        namespace: String,
+        dry_run: bool,
+    },
+    Navi {
+        dir: PathBuf,
+        dry_run: bool,
+    },
+}
+
+impl ImportEngine {
+    pub fn run(mut self, cmd: ImportCommand) -> Result<i32> {
+        match cmd {
+            ImportCommand::Shell {
+                file,
+                namespace,
+                dry_run,
+            } => {
+                let content = std::fs::read_to_string(&file)
+                    .map_err(|e| ErrorImportEngine::Source(file.clone(), e))?;
+                self.save(&namespace, import_shell(&content), dry_run || self.dry)
+            }
+            ImportCommand::Navi { dir, dry_run } => self.navi(&dir, dry_run || self.dry),
+        }
+    }
+
+    // every `.cheat` file is converted, the sections sharing a namespace are merged.
+    fn navi(&mut self, dir: &Path, dry_run: bool) -> Result<i32> {
+        let mut files: Vec<PathBuf> = walk_dir(dir)?
+            .into_iter()
+            .filter(|f| f.is_file() && f.extension().map(|e| e == "cheat").unwrap_or(false))
+            .collect();
+        files.sort();
+
+        let mut namespaces: BTreeMap<String, Imported> = BTreeMap::new();
+        for file in files {
+            let content = std::fs::read_to_string(&file)
+                .map_err(|e| ErrorImportEngine::Source(file.clone(), e))?;
+            let source = file
+                .strip_prefix(dir)
+                .unwrap_or(&file)
+                .display()
+                .to_string();
+            let default_namespace = file
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+            for (namespace, imported) in import_navi(&content, &source, &default_namespace) {
+                namespaces.entry(namespace).or_default().merge(imported);
+            }
+        }
+
+        for (namespace, imported) in namespaces {
+            self.save(&namespace, imported, dry_run)?;
+        }
+        Ok(0)
+    }
+
+    // writes what was imported in the namespace directory of the first root directory,
+    // definitions that already exist are left untouched.
+    fn save(&mut self, namespace: &str, mut imported: Imported, dry_run: bool) -> Result<i32> {
+        let root = self.root_dirs.first().ok_or(ErrorImportEngine::NoRootDir)?;
+        let dir = root.join(namespace);
+
+        let mut aliases = vec![];
+        for mut alias in imported.aliases {
+            NamespaceUpdater::update(&mut alias, namespace);
+            if self.aliases.get(&alias.identifier()).is_ok() {
+                imported.skipped.push(Skipped::new(
+                    format!("alias {}", alias.identifier()),
+                    "already defined",
+                ));
+            } else {
+                aliases.push(alias);
+            }
+        }
+        let mut vars = vec![];
+        for mut var in imported.vars {
+            var.update(namespace);
+            if self.vars.get(&var.name()).is_some() {
+                imported.skipped.push(Skipped::new(
+                    format!("var {}", var.name()),
+                    "already defined",
+                ));
+            } else {
+                vars.push(var);
+            }
+        }
+
+        let files = [
+            (dir.join("aliases.yaml"), aliases_to_yaml(&aliases)?),
+            (dir.join("vars.yaml"), vars_to_yaml(&vars)?),
+        ];
+        for (path, addition) in files.iter().filter(|(_, a)| !a.is_empty()) {
+            if dry_run {
+                let existing = std::fs::read_to_string(path).ok();
+                let diff = append_diff(path, existing.as_deref(), addition);
+                self.output.write_all(diff.as_bytes())?;
+            } else {
+                append_to_path(path, addition)?;
+            }
+        }
+
+        // the summary goes to stderr so that the diff can be piped as is.
+        eprintln!(
+            "{}{}{} aliases and {} vars into namespace {}{}",
+            termion::style::Bold,
+            if dry_run {
+                "would import "
+            } else {
+                "imported "
+            },
+            aliases.len(),
+            vars.len(),
+            namespace,
+            termion::style::Reset,
+        );
+        if !imported.skipped.is_empty() {
+            eprintln!("the following could not be imported:");
+            for skipped in &imported.skipped {
+                eprintln!(
+                    "- {}{}{}",
+                    termion::color::Fg(termion::color::Yellow),
+                    skipped,
+                    termion::style::Reset,
+                );
+            }
+        }
+        Ok(0)
+    }
+}
+
+// new definitions are only ever appended, the diff is a single hunk at the end of the file.
+fn append_diff(path: &Path, existing: Option<&str>, addition: &str) -> String {
+    let (source, start) = match existing {
+        Some(content) => (path.display().to_string(), content.lines().count()),
+        None => (String::from("/dev/null"), 0),
+    };
+    let added: Vec<&str> = addition.lines().collect();
+    let mut diff = format!(
+        "--- {}\n+++ {}\n@@ -{},0 +{},{} @@\n",
+        source,
+        path.display(),
+        start,
+        start + 1,
+        added.len()
+    );
+    for line in added {
+        diff.push('+');
+        diff.push_str(line);
+        diff.push('\n');
+    }
+    diff
+}
+
+type Result<T> = std::result::Result<T, ErrorImportEngine>;
+
+#[derive(Debug, Error)]
+pub enum ErrorImportEngine {
+    #[error("could not read {0}\n-> {1}")]
+    Source(PathBuf, std::io::Error),
+    #[error("could not list the files to import\n-> {0}")]
+    Walk(#[from] ErrorsFS),
+    #[error("a root directory is needed to import definitions, check the root_dir setting")]
+    NoRootDir,
+    #[error("could not serialize the imported definitions\n-> {0}")]
+    Serde(#[from] ErrorsSerde),
+    #[error("could not write the imported definitions\n-> {0}")]
+    Output(#[from] std::io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::append_diff;
+    use std::path::Path;
+
+    #[test]
+    fn test_append_diff() {
+        let path = Path::new("/root/ns/aliases.yaml");
+        let addition = "- name: ll\n  desc: ll\n  alias: ls -la\n";
+        assert_eq!(
+            append_diff(path, None, addition),
+            "--- /dev/null\n+++ /root/ns/aliases.yaml\n@@ -0,0 +1,3 @@\n\
+             +- name: ll\n+  desc: ll\n+  alias: ls -la\n"
+        );
+        assert_eq!(
+            append_diff(path, Some("- name: a\n  desc: a\n  alias: a\n"), addition),
+            "--- /root/ns/aliases.yaml\n+++ /root/ns/aliases.yaml\n@@ -3,0 +4,3 @@\n\
+             +- name: ll\n+  desc: ll\n+  alias: ls -la\n"
+        );
+    }
+}
@@ -6,7 +6,7 @@ use sam_core::engines::SamLogger;
 pub struct StdErrLogger;
 impl SamLogger for StdErrLogger {
     fn final_command(&self, alias: &Alias, fc: &dyn Display) {
-        println!(
+        eprintln!(
             "{}{}[SAM][ alias='{}::{}']{} Running final command: {}{}'{}'{}",
             termion::color::Fg(termion::color::Green),
             termion::style::Bold,
@@ -3,19 +3,36 @@ use crate::config_engine::ErrorsConfigEngine;
 use crate::environment::ErrorEnvironment;
 use cache_engine::ErrorCacheEngine;
 use cli::SubCommand;
+use completion_engine::{CompletionCommand, ErrorCompletionEngine};
+use export_engine::ErrorExportEngine;
+use history_engine::ErrorHistoryEngine;
+use import_engine::ErrorImportEngine;
 use preview_engine::ErrorsPreviewEngine;
+use resolve_engine::ErrorResolveEngine;
 use sam_core::engines::ErrorSamEngine;
+use stats_engine::ErrorStatsEngine;
 use std::collections::HashMap;
 use thiserror::Error;
 
 mod cache_engine;
 mod cli;
+mod completion_engine;
 mod config;
 mod config_engine;
+mod env_values;
 mod environment;
 mod executors;
+mod export_engine;
+mod history_engine;
+mod import_engine;
 mod logger;
 mod preview_engine;
+mod resolve_engine;
+mod stats_engine;
+
+const EXIT_FAILURE: i32 = 1;
+// scripts can tell a var that is missing a choice apart from other failures.
+const EXIT_INTERACTION_REQUIRED: i32 = 2;
 
 fn main() {
     match run() {
@@ -27,14 +44,30 @@ fn main() {
                 termion::style::Bold,
                 termion::style::Reset,
                 err,
-            )
+            );
+            std::process::exit(err.exit_code())
         }
     }
 }
 
 fn run() -> Result<i32> {
     let cli_request = cli::read_cli_request()?;
-    let app_config = AppSettings::load(Some(cli_request.settings))?;
+    // the completion scripts and the widgets only depend on the command line definition.
+    match cli_request.command {
+        SubCommand::CompletionCommand(CompletionCommand::Script { shell }) => {
+            completion_engine::write_script(shell, &mut std::io::stdout())?;
+            return Ok(0);
+        }
+        SubCommand::CompletionCommand(CompletionCommand::Widget { shell }) => {
+            completion_engine::write_widget(shell, &mut std::io::stdout())?;
+            return Ok(0);
+        }
+        _ => {}
+    }
+    let mut app_config = AppSettings::load(Some(cli_request.settings))?;
+    if cli_request.command.needs_env_variables() {
+        app_config.resolve_env_variables()?;
+    }
     let environment = environment::from_settings(app_config)?;
     run_command(cli_request.command, environment)
 }
@@ -45,11 +78,32 @@ fn run_command(sub_command: SubCommand, env: environment::Environment) -> Result
         SubCommand::CacheCommand(s) => Ok(env.cache_engine().run(s)?),
         SubCommand::ConfigCheck(s) => Ok(env.config_engine().run(s)?),
         SubCommand::PreviewCommand(s) => Ok(env.preview_engine().run(s)?),
+        SubCommand::HistoryCommand(s) => Ok(env.history_engine().run(s)?),
+        SubCommand::StatsCommand(s) => Ok(env.stats_engine().run(s)?),
+        SubCommand::ImportCommand(s) => Ok(env.import_engine().run(s)?),
+        SubCommand::ExportCommand(s) => Ok(env.export_engine().run(s)?),
+        SubCommand::CompletionCommand(s) => Ok(env.completion_engine().run(s)?),
+        SubCommand::ResolveCommand(s) => Ok(env.resolve_engine().run(s)?),
     }
 }
 
 type Result<T> = std::result::Result<T, ErrorMain>;
 
+impl ErrorMain {
+    fn exit_code(&self) -> i32 {
+        let sam_engine_error = match self {
+            ErrorMain::SamEngine(e)
+            | ErrorMain::HistoryEngine(ErrorHistoryEngine::SamEngine(e))
+            | ErrorMain::ResolveEngine(ErrorResolveEngine::SamEngine(e)) => Some(e),
+            _ => None,
+        };
+        match sam_engine_error {
+            Some(e) if e.requires_interaction() => EXIT_INTERACTION_REQUIRED,
+            _ => EXIT_FAILURE,
+        }
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum ErrorMain {
     #[error("Configuration file contains invalid settings \n-> {0}")]
@@ -66,4 +120,44 @@ pub enum ErrorMain {
     ConfigError(#[from] ErrorsConfigEngine),
     #[error("{0}")]
     PreviewEngine(#[from] ErrorsPreviewEngine),
+    #[error("{0}")]
+    HistoryEngine(#[from] ErrorHistoryEngine),
+    #[error("{0}")]
+    StatsEngine(#[from] ErrorStatsEngine),
+    #[error("{0}")]
+    ImportEngine(#[from] ErrorImportEngine),
+    #[error("{0}")]
+    ExportEngine(#[from] ErrorExportEngine),
+    #[error("{0}")]
+    CompletionEngine(#[from] ErrorCompletionEngine),
+    #[error("{0}")]
+    ResolveEngine(#[from] ErrorResolveEngine),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ErrorMain, EXIT_FAILURE, EXIT_INTERACTION_REQUIRED};
+    use crate::resolve_engine::ErrorResolveEngine;
+    use sam_core::algorithms::ErrorDependencyResolution;
+    use sam_core::engines::ErrorSamEngine;
+    use sam_core::entities::dependencies::ErrorsResolver;
+    use sam_core::entities::identifiers::{Identifier, Identifiers};
+
+    #[test]
+    fn test_exit_code() {
+        let unresolved = ErrorDependencyResolution::UnresolvedVars(Identifiers(vec![
+            Identifier::with_namespace("var", Some("ns")),
+        ]));
+        let err = ErrorMain::SamEngine(ErrorSamEngine::from(unresolved));
+        assert_eq!(err.exit_code(), EXIT_INTERACTION_REQUIRED);
+
+        let required = ErrorsResolver::InteractionRequired(Identifier::new("var"));
+        let err = ErrorMain::ResolveEngine(ErrorResolveEngine::SamEngine(
+            ErrorSamEngine::Resolver(required),
+        ));
+        assert_eq!(err.exit_code(), EXIT_INTERACTION_REQUIRED);
+
+        let err = ErrorMain::SamEngine(ErrorSamEngine::InvalidAliasSelection);
+        assert_eq!(err.exit_code(), EXIT_FAILURE);
+    }
 }
@@ -2,7 +2,7 @@ use std::{collections::HashMap, io::Write};
 use thiserror::Error;
 
 use sam_core::{
-    algorithms::{execution_sequence_for_dependencies, ErrorDependencyResolution},
+    algorithms::{execution_sequence_for_dependencies, ErrorDependencyResolution, VarsCollection},
     engines::{AliasCollection, ErrorsAliasCollection},
     entities::aliases::Alias,
     entities::choices::Choice,
@@ -36,8 +36,16 @@ impl PreviewEngine {
     }
 
     fn preview_alias(&mut self, alias_id: Identifier) -> Result<i32> {
-        let choices: &HashMap<Identifier, Choice> = &self.defaults;
-        let alias: Alias = self.aliases.get(&alias_id)?.with_partial_choices(choices);
+        // the values of secret vars are never displayed.
+        let choices: HashMap<Identifier, Choice> = self
+            .defaults
+            .iter()
+            .map(|(id, choice)| match self.vars.is_secret(id) {
+                true => (id.clone(), Choice::redacted()),
+                false => (id.clone(), choice.clone()),
+            })
+            .collect();
+        let alias: Alias = self.aliases.get(&alias_id)?.with_partial_choices(&choices);
         let exec_seq = execution_sequence_for_dependencies(&self.vars, alias.clone())?;
 
         write!(
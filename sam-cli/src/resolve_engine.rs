@@ -0,0 +1,187 @@
+use sam_core::engines::{ErrorSamEngine, SamEngine};
+use sam_core::entities::aliases::ResolvedAlias;
+use sam_core::entities::identifiers::Identifier;
+use sam_persistence::repositories::{AliasesRepository, VarsRepository};
+use sam_tui::UserInterface;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use thiserror::Error;
+
+pub struct ResolveEngine {
+    pub sam_engine: SamEngine<UserInterface, AliasesRepository, VarsRepository, VarsRepository>,
+    pub output: Box<dyn Write>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResolveCommand {
+    Resolve {
+        alias: Identifier,
+        format: ResolveFormat,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResolveFormat {
+    Json,
+    // the resolved command as a single quoted shell word.
+    Shell,
+    // `KEY=value` lines that can be sourced by a shell.
+    Env,
+}
+
+impl ResolveEngine {
+    pub fn run(mut self, cmd: ResolveCommand) -> Result<i32> {
+        match cmd {
+            ResolveCommand::Resolve { alias, format } => {
+                let resolved = self.sam_engine.resolve_alias(&alias)?;
+                let env = &self.sam_engine.env_variables;
+                write_resolved(&mut self.output, format, &resolved, env)?;
+            }
+        }
+        Ok(0)
+    }
+}
+
+// JsonResolvedAlias is the json representation of a resolved alias,
+// the choices are keyed by the full var identifier.
+#[derive(Serialize)]
+struct JsonResolvedAlias<'a> {
+    name: String,
+    description: &'a str,
+    original: &'a str,
+    resolved: &'a str,
+    choices: BTreeMap<String, JsonChoice<'a>>,
+    env: BTreeMap<&'a str, &'a str>,
+}
+
+#[derive(Serialize)]
+struct JsonChoice<'a> {
+    value: &'a str,
+    description: Option<&'a str>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    secret: bool,
+}
+
+fn write_resolved(
+    output: &mut dyn Write,
+    format: ResolveFormat,
+    resolved: &ResolvedAlias,
+    env: &HashMap<String, String>,
+) -> Result<()> {
+    match format {
+        ResolveFormat::Json => {
+            let json = JsonResolvedAlias {
+                name: resolved.name().to_string(),
+                description: resolved.desc(),
+                original: resolved.original_alias(),
+                resolved: resolved.resolved_alias(),
+                choices: resolved
+                    .choices()
+                    .iter()
+                    .map(|(id, choice)| {
+                        let choice = JsonChoice {
+                            value: choice.value(),
+                            description: choice.desc(),
+                            secret: resolved.is_secret(id),
+                        };
+                        (id.to_string(), choice)
+                    })
+                    .collect(),
+                env: env.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect(),
+            };
+            writeln!(output, "{}", serde_json::to_string_pretty(&json)?)?;
+        }
+        ResolveFormat::Shell => writeln!(output, "{}", shell_quote(resolved.resolved_alias()))?,
+        ResolveFormat::Env => {
+            let mut lines: BTreeMap<String, String> = env
+                .iter()
+                .map(|(key, value)| (key.clone(), shell_quote(value)))
+                .collect();
+            for (id, choice) in resolved.choices() {
+                lines.insert(id.env_name(), shell_quote(choice.value()));
+            }
+            let name = resolved.name().to_string();
+            lines.insert(String::from("SAM_ALIAS"), shell_quote(&name));
+            let command = shell_quote(resolved.resolved_alias());
+            lines.insert(String::from("SAM_COMMAND"), command);
+            for (key, value) in lines {
+                writeln!(output, "{}={}", key, value)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+type Result<T> = std::result::Result<T, ErrorResolveEngine>;
+
+#[derive(Debug, Error)]
+pub enum ErrorResolveEngine {
+    #[error("{0}")]
+    SamEngine(#[from] ErrorSamEngine),
+    #[error("could not serialize the resolved alias\n -> {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("could not write the resolved alias\n -> {0}")]
+    Output(#[from] std::io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_resolved, ResolveFormat};
+    use sam_core::entities::aliases::ResolvedAlias;
+    use sam_core::entities::choices::Choice;
+    use sam_core::entities::identifiers::Identifier;
+    use std::collections::HashMap;
+
+    fn output(format: ResolveFormat) -> String {
+        let token = Identifier::with_namespace("token", Some("ns"));
+        let alias = ResolvedAlias::new(
+            Identifier::with_namespace("login", Some("ns")),
+            String::from("logs in"),
+            String::from("login --user '{{user}}' --token {{token}}"),
+            String::from("login --user 'me' --token s3cr3t"),
+            maplit::hashmap! {
+                Identifier::with_namespace("user", Some("ns")) => Choice::new("me", Some("the user")),
+                token.clone() => Choice::from_value("s3cr3t"),
+            },
+        )
+        .with_secrets(vec![token]);
+        let env: HashMap<String, String> = maplit::hashmap! {
+            String::from("REGION") => String::from("eu-west-1"),
+        };
+        let mut output = vec![];
+        write_resolved(&mut output, format, &alias, &env).expect("should write the alias");
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_resolve_json() {
+        let json: serde_json::Value = serde_json::from_str(&output(ResolveFormat::Json)).unwrap();
+        assert_eq!(json["name"], "ns::login");
+        assert_eq!(json["resolved"], "login --user 'me' --token s3cr3t");
+        assert_eq!(json["choices"]["ns::user"]["description"], "the user");
+        assert_eq!(json["choices"]["ns::user"].get("secret"), None);
+        assert_eq!(json["choices"]["ns::token"]["secret"], true);
+        assert_eq!(json["env"]["REGION"], "eu-west-1");
+    }
+
+    #[test]
+    fn test_resolve_shell_and_env() {
+        assert_eq!(
+            output(ResolveFormat::Shell),
+            "'login --user '\\''me'\\'' --token s3cr3t'\n"
+        );
+        assert_eq!(
+            output(ResolveFormat::Env),
+            "REGION='eu-west-1'\n\
+             SAM_ALIAS='ns::login'\n\
+             SAM_COMMAND='login --user '\\''me'\\'' --token s3cr3t'\n\
+             SAM_NS_TOKEN='s3cr3t'\n\
+             SAM_NS_USER='me'\n"
+        );
+    }
+}
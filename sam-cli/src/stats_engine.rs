@@ -0,0 +1,341 @@
+use crate::environment::HISTORY_MAX_SIZE;
+use sam_core::entities::aliases::Alias;
+use sam_core::entities::history::HistoryEntry;
+use sam_core::entities::namespaces::Namespace;
+use sam_persistence::repositories::AliasesRepository;
+use sam_persistence::{AliasHistory, ErrorAliasHistory};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::io::Write;
+use std::path::PathBuf;
+use thiserror::Error;
+
+const TOP_CHOICES_PER_VAR: usize = 5;
+
+pub struct StatsEngine {
+    pub history_file: PathBuf,
+    pub aliases: AliasesRepository,
+    pub output: Box<dyn Write>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StatsFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatsCommand {
+    Report { format: StatsFormat },
+}
+
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct UsageReport {
+    pub aliases: Vec<AliasUsage>,
+    pub namespaces: Vec<NamespaceUsage>,
+    pub choices: Vec<VarUsage>,
+    pub never_run: Vec<String>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct AliasUsage {
+    pub alias: String,
+    pub runs: usize,
+    pub failures: usize,
+    /// only accounts for the executions whose exit code was recorded.
+    pub failure_rate: Option<f64>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct NamespaceUsage {
+    pub namespace: String,
+    pub runs: usize,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct VarUsage {
+    pub var: String,
+    pub values: Vec<ChoiceUsage>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ChoiceUsage {
+    pub value: String,
+    pub count: usize,
+}
+
+impl StatsEngine {
+    pub fn run(mut self, cmd: StatsCommand) -> Result<i32> {
+        match cmd {
+            StatsCommand::Report { format } => self.report(format),
+        }
+    }
+
+    fn report(&mut self, format: StatsFormat) -> Result<i32> {
+        let history = AliasHistory::new(&self.history_file, Some(HISTORY_MAX_SIZE))?;
+        let report = usage_report(&history.entries()?, &self.aliases.aliases());
+        match format {
+            StatsFormat::Table => self.write_table(&report)?,
+            StatsFormat::Json => {
+                serde_json::to_writer_pretty(&mut self.output, &report)?;
+                writeln!(self.output)?;
+            }
+            StatsFormat::Csv => write_csv(&mut self.output, &report)?,
+        }
+        Ok(0)
+    }
+
+    fn write_table(&mut self, report: &UsageReport) -> Result<()> {
+        self.write_title("Most run aliases")?;
+        writeln!(
+            self.output,
+            "{:<40} {:>6} {:>9} {:>13}",
+            "alias", "runs", "failures", "failure rate"
+        )?;
+        for usage in &report.aliases {
+            let rate = usage
+                .failure_rate
+                .map(|r| format!("{:.1}%", r * 100.0))
+                .unwrap_or_else(|| String::from("-"));
+            writeln!(
+                self.output,
+                "{:<40} {:>6} {:>9} {:>13}",
+                usage.alias, usage.runs, usage.failures, rate
+            )?;
+        }
+
+        self.write_title("Runs per namespace")?;
+        for usage in &report.namespaces {
+            writeln!(self.output, "{:<40} {:>6}", usage.namespace, usage.runs)?;
+        }
+
+        self.write_title("Most common choices")?;
+        for usage in &report.choices {
+            let values: Vec<String> = usage
+                .values
+                .iter()
+                .map(|c| format!("{} ({})", c.value, c.count))
+                .collect();
+            writeln!(self.output, "{:<40} {}", usage.var, values.join(", "))?;
+        }
+
+        self.write_title("Aliases never run")?;
+        for alias in &report.never_run {
+            writeln!(self.output, "- {}", alias)?;
+        }
+        Ok(())
+    }
+
+    fn write_title(&mut self, title: &str) -> Result<()> {
+        writeln!(
+            self.output,
+            "\n{}{}{}{}\n",
+            termion::style::Bold,
+            termion::color::Fg(termion::color::Green),
+            title,
+            termion::style::Reset,
+        )?;
+        Ok(())
+    }
+}
+
+pub fn usage_report(entries: &[HistoryEntry], aliases: &[Alias]) -> UsageReport {
+    let mut runs: HashMap<String, (usize, usize, usize)> = HashMap::new();
+    let mut namespaces: HashMap<String, usize> = HashMap::new();
+    let mut choices: HashMap<String, HashMap<String, usize>> = HashMap::new();
+    for entry in entries {
+        // (runs, failures, executions with a known exit code)
+        let counts = runs.entry(entry.alias.name().to_string()).or_default();
+        counts.0 += 1;
+        if entry.exit_code.is_some() {
+            counts.2 += 1;
+        }
+        if entry.failed() {
+            counts.1 += 1;
+        }
+        let namespace = entry.alias.namespace().unwrap_or_default().to_string();
+        *namespaces.entry(namespace).or_default() += 1;
+        for (var, choice) in entry.alias.choices() {
+            if entry.alias.is_secret(var) {
+                continue;
+            }
+            *choices
+                .entry(var.to_string())
+                .or_default()
+                .entry(choice.value().to_string())
+                .or_default() += 1;
+        }
+    }
+
+    let never_run: Vec<String> = aliases
+        .iter()
+        .map(|a| a.identifier().to_string())
+        .filter(|id| !runs.contains_key(id))
+        .collect::<BTreeSet<String>>()
+        .into_iter()
+        .collect();
+
+    let mut aliases: Vec<AliasUsage> = runs
+        .into_iter()
+        .map(|(alias, (runs, failures, known))| AliasUsage {
+            alias,
+            runs,
+            failures,
+            failure_rate: if known > 0 {
+                Some(failures as f64 / known as f64)
+            } else {
+                None
+            },
+        })
+        .collect();
+    aliases.sort_by(|a, b| b.runs.cmp(&a.runs).then_with(|| a.alias.cmp(&b.alias)));
+
+    let mut namespaces: Vec<NamespaceUsage> = namespaces
+        .into_iter()
+        .map(|(namespace, runs)| NamespaceUsage { namespace, runs })
+        .collect();
+    namespaces.sort_by(|a, b| {
+        b.runs
+            .cmp(&a.runs)
+            .then_with(|| a.namespace.cmp(&b.namespace))
+    });
+
+    let mut choices: Vec<VarUsage> = choices
+        .into_iter()
+        .map(|(var, values)| {
+            let mut values: Vec<ChoiceUsage> = values
+                .into_iter()
+                .map(|(value, count)| ChoiceUsage { value, count })
+                .collect();
+            values.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+            values.truncate(TOP_CHOICES_PER_VAR);
+            VarUsage { var, values }
+        })
+        .collect();
+    choices.sort_by(|a, b| a.var.cmp(&b.var));
+
+    UsageReport {
+        aliases,
+        namespaces,
+        choices,
+        never_run,
+    }
+}
+
+// every row follows the same `kind,name,value,runs,failures` layout
+// so that the whole report fits in a single csv document.
+fn write_csv(output: &mut dyn Write, report: &UsageReport) -> Result<()> {
+    writeln!(output, "kind,name,value,runs,failures")?;
+    for usage in &report.aliases {
+        writeln!(
+            output,
+            "alias,{},,{},{}",
+            csv_field(&usage.alias),
+            usage.runs,
+            usage.failures
+        )?;
+    }
+    for usage in &report.namespaces {
+        writeln!(
+            output,
+            "namespace,{},,{},",
+            csv_field(&usage.namespace),
+            usage.runs
+        )?;
+    }
+    for usage in &report.choices {
+        for choice in &usage.values {
+            writeln!(
+                output,
+                "choice,{},{},{},",
+                csv_field(&usage.var),
+                csv_field(&choice.value),
+                choice.count
+            )?;
+        }
+    }
+    for alias in &report.never_run {
+        writeln!(output, "never_run,{},,0,", csv_field(alias))?;
+    }
+    Ok(())
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+type Result<T> = std::result::Result<T, ErrorStatsEngine>;
+
+#[derive(Debug, Error)]
+pub enum ErrorStatsEngine {
+    #[error("could not read the history\n -> {0}")]
+    History(#[from] ErrorAliasHistory),
+    #[error("could not write the report\n -> {0}")]
+    Output(#[from] std::io::Error),
+    #[error("could not serialize the report\n -> {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{usage_report, write_csv};
+    use sam_core::entities::aliases::{Alias, ResolvedAlias};
+    use sam_core::entities::choices::Choice;
+    use sam_core::entities::history::{ExecutionOutcome, HistoryEntry};
+    use sam_core::entities::identifiers::Identifier;
+    use std::time::Duration;
+
+    fn entry(alias: &str, cluster: &str, exit_code: Option<i32>) -> HistoryEntry {
+        let alias = ResolvedAlias::new(
+            Identifier::from_str(alias),
+            String::from("desc"),
+            String::from("kubectl --context {{cluster}} get pods"),
+            format!("kubectl --context {} get pods", cluster),
+            maplit::hashmap! {
+                Identifier::with_namespace("cluster", Some("k8s")) => Choice::from_value(cluster),
+            },
+        );
+        HistoryEntry::new(alias, "/tmp").with_outcome(&ExecutionOutcome {
+            exit_code,
+            duration: Duration::from_millis(1),
+            printed: false,
+        })
+    }
+
+    #[test]
+    fn test_usage_report() {
+        let entries = vec![
+            entry("k8s::pods", "prod", Some(0)),
+            entry("k8s::pods", "prod", Some(1)),
+            entry("k8s::pods", "dev", None),
+            entry("k8s::logs", "prod", Some(0)),
+        ];
+        let aliases = vec![
+            Alias::from(entry("k8s::pods", "prod", None).alias),
+            Alias::from(entry("k8s::nodes", "prod", None).alias),
+        ];
+        let report = usage_report(&entries, &aliases);
+
+        assert_eq!(report.aliases[0].alias, "k8s::pods");
+        assert_eq!(report.aliases[0].runs, 3);
+        assert_eq!(report.aliases[0].failures, 1);
+        assert_eq!(report.aliases[0].failure_rate, Some(0.5));
+        assert_eq!(report.namespaces[0].runs, 4);
+        assert_eq!(report.choices[0].var, "k8s::cluster");
+        assert_eq!(report.choices[0].values[0].value, "prod");
+        assert_eq!(report.choices[0].values[0].count, 3);
+        assert_eq!(report.never_run, vec![String::from("k8s::nodes")]);
+
+        let mut csv = vec![];
+        write_csv(&mut csv, &report).expect("should write the csv");
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.starts_with("kind,name,value,runs,failures\n"));
+        assert!(csv.contains("alias,k8s::pods,,3,1\n"));
+        assert!(csv.contains("never_run,k8s::nodes,,0,\n"));
+    }
+}
@@ -15,6 +15,22 @@ use thiserror::Error;
 
 pub trait VarsCollection {
     fn get(&self, id: &Identifier) -> Option<&Var>;
+
+    /// true for secret vars and for the vars whose command embeds the choice of a secret var,
+    /// their choices are never cached, recorded or displayed.
+    fn is_secret(&self, id: &Identifier) -> bool {
+        let mut seen = HashSet::new();
+        let mut candidates = vec![id.clone()];
+        while let Some(cur) = candidates.pop() {
+            if let Some(var) = self.get(&cur).filter(|_| seen.insert(cur.clone())) {
+                if var.is_secret() {
+                    return true;
+                }
+                candidates.extend(var.dependencies());
+            }
+        }
+        false
+    }
 }
 
 pub trait VarsDefaultValues {
@@ -72,6 +88,8 @@ pub enum ErrorDependencyResolution {
     MissingDependencies(Identifiers),
     #[error("the provided variables are unknown:\n{0}")]
     UnknowVarsDefaults(Identifiers),
+    #[error("the following vars need a choice but sam can't prompt for them, set them with `-c ns::var=<value>` or `SAM_<NS>_<VAR>` environment variables:\n{0}")]
+    UnresolvedVars(Identifiers),
     #[error("no choices available for var {var_name}\n-> {error}")]
     NoChoiceForVar {
         var_name: Identifier,
@@ -87,12 +105,29 @@ pub fn choices_for_execution_sequence<'a, R: Resolver>(
     vars: ExecutionSequence<'a>,
 ) -> std::result::Result<Vec<(Identifier, Choice)>, ErrorDependencyResolution> {
     let mut choices: HashMap<Identifier, Choice> = HashMap::new();
+    // the vars the resolver can't prompt for, and the vars that depend on them, are all reported at once.
+    let mut unresolved: Vec<Identifier> = Vec::new();
     for var_name in vars.as_slice() {
         if let Some(var) = vars_col.get(*var_name) {
             let choice = if let Some(default) = vars_defaults.default_value(&var.name()) {
-                default.to_owned()
+                Ok(default.to_owned())
+            } else if var.dependencies().iter().any(|d| unresolved.contains(d)) {
+                unresolved.push(var.name());
+                continue;
+            } else if vars_col.is_secret(&var.name()) && !var.is_secret() {
+                choice_for_var(resolver, &var.clone().with_secret(true), &choices)
             } else {
-                choice_for_var(resolver, var, &choices)?
+                choice_for_var(resolver, var, &choices)
+            };
+            let choice = match choice {
+                Err(ErrorDependencyResolution::NoChoiceForVar {
+                    var_name,
+                    error: ErrorsResolver::InteractionRequired(_),
+                }) => {
+                    unresolved.push(var_name);
+                    continue;
+                }
+                choice => choice?,
             };
             choices.insert(var.name(), choice);
         } else {
@@ -101,6 +136,11 @@ pub fn choices_for_execution_sequence<'a, R: Resolver>(
             )));
         }
     }
+    if !unresolved.is_empty() {
+        return Err(ErrorDependencyResolution::UnresolvedVars(Identifiers(
+            unresolved,
+        )));
+    }
     Ok(choices.into_iter().collect())
 }
 
@@ -135,7 +175,11 @@ where
 {
     if var.is_command() {
         let command = var.substitute_for_choices(choices)?;
-        resolver.resolve_dynamic(var.name(), ShellCommand::new(command))
+        if var.is_secret() {
+            resolver.resolve_dynamic_uncached(var.name(), ShellCommand::new(command))
+        } else {
+            resolver.resolve_dynamic(var.name(), ShellCommand::new(command))
+        }
     } else if var.is_input() {
         let prompt = var.prompt().unwrap_or("no provided prompt");
         resolver.resolve_input(var.name(), prompt)
@@ -174,10 +218,15 @@ mod tests {
         VarsCollectionMock, VarsDefaultValuesMock,
     };
     use crate::algorithms::dependency_resolution::resolve_choice_for_var;
+    use crate::algorithms::ErrorDependencyResolution;
+    use crate::algorithms::VarsCollection;
     use crate::algorithms::{choices_for_execution_sequence, execution_sequence_for_dependencies};
     use crate::entities::choices::Choice;
     use crate::entities::dependencies::mocks::StaticResolver;
+    use crate::entities::dependencies::{ErrorsResolver, Resolver};
     use crate::entities::identifiers::fixtures::*;
+    use crate::entities::identifiers::{Identifier, Identifiers};
+    use crate::entities::processes::ShellCommand;
     use crate::entities::vars::fixtures::*;
     use maplit::hashmap;
 
@@ -264,4 +313,70 @@ mod tests {
         .sort();
         assert_eq!(res.unwrap().sort(), expected);
     }
+
+    // a resolver that is never allowed to prompt.
+    struct PromptlessResolver;
+    impl Resolver for PromptlessResolver {
+        fn resolve_input(&self, var: Identifier, _: &str) -> Result<Choice, ErrorsResolver> {
+            Err(ErrorsResolver::InteractionRequired(var))
+        }
+        fn resolve_dynamic<CMD>(&self, var: Identifier, _: CMD) -> Result<Choice, ErrorsResolver>
+        where
+            CMD: Into<ShellCommand<String>>,
+        {
+            Err(ErrorsResolver::InteractionRequired(var))
+        }
+        fn resolve_static(
+            &self,
+            var: Identifier,
+            _: impl Iterator<Item = Choice>,
+        ) -> Result<Choice, ErrorsResolver> {
+            Err(ErrorsResolver::InteractionRequired(var))
+        }
+        fn select_identifier(
+            &self,
+            _: &[Identifier],
+            _: Option<&[&str]>,
+            _: &str,
+        ) -> Result<Identifier, ErrorsResolver> {
+            Err(ErrorsResolver::IdentifierSelectionEmpty())
+        }
+    }
+
+    #[test]
+    fn test_unresolved_vars_are_all_reported() {
+        let full = vec![
+            VAR_DIRECTORY.clone(),
+            VAR_LISTING.clone(),
+            VAR_PATTERN.clone(),
+        ];
+        let repo = VarsCollectionMock(full.into_iter().map(|c| (c.name(), c)).collect());
+        let defaults = VarsDefaultValuesMock(hashmap! {
+            VAR_DIRECTORY_NAME.clone() => VAR_DIRECTORY_CHOICE_1.clone(),
+        });
+        let seq = execution_sequence_for_dependencies(&repo, VAR_USE_LISTING.clone()).unwrap();
+        let res = choices_for_execution_sequence(&repo, &defaults, &PromptlessResolver, seq);
+        match res {
+            Err(ErrorDependencyResolution::UnresolvedVars(Identifiers(unresolved))) => assert_eq!(
+                unresolved,
+                vec![VAR_PATTERN_NAME.clone(), VAR_LISTING_NAME.clone()]
+            ),
+            other => panic!("the unresolved vars should be reported, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_vars_depending_on_a_secret_are_secret() {
+        let full = vec![
+            VAR_DIRECTORY.clone().with_secret(true),
+            VAR_LISTING.clone(),
+            VAR_PATTERN.clone(),
+            VAR_USE_LISTING.clone(),
+        ];
+        let repo = VarsCollectionMock(full.into_iter().map(|c| (c.name(), c)).collect());
+        assert!(repo.is_secret(&VAR_DIRECTORY_NAME));
+        assert!(repo.is_secret(&VAR_LISTING_NAME));
+        assert!(repo.is_secret(&VAR_USE_LISTING_NAME));
+        assert!(!repo.is_secret(&VAR_PATTERN_NAME));
+    }
 }
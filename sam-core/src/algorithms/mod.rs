@@ -1,4 +1,5 @@
 mod dependency_resolution;
+mod warmup;
 
 pub use dependency_resolution::choice_for_var;
 pub use dependency_resolution::choices_for_execution_sequence;
@@ -6,6 +7,7 @@ pub use dependency_resolution::execution_sequence_for_dependencies;
 pub use dependency_resolution::ErrorDependencyResolution;
 pub use dependency_resolution::VarsCollection;
 pub use dependency_resolution::VarsDefaultValues;
+pub use warmup::warmup_commands;
 
 #[cfg(test)]
 pub mod mocks {
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use crate::entities::{
+    choices::Choice,
+    commands::Command,
+    dependencies::{Dependencies, ExecutionSequence},
+    identifiers::Identifier,
+};
+
+use super::{VarsCollection, VarsDefaultValues};
+
+/// returns the commands that can be run ahead of time for the `from_command` vars
+/// of the provided execution sequence.
+/// A `from_command` var can be warmed up when each of its dependencies either has a default
+/// value or is a var with a static list of choices. In the latter case, one command is returned
+/// for every possible combination of the upstream static choices.
+pub fn warmup_commands(
+    vars: &dyn VarsCollection,
+    defaults: &dyn VarsDefaultValues,
+    seq: &ExecutionSequence<'_>,
+) -> Vec<(Identifier, String)> {
+    let mut commands = vec![];
+    for id in seq.as_slice() {
+        let var = match vars.get(id) {
+            Some(var)
+                if var.is_command()
+                    && !vars.is_secret(id)
+                    && defaults.default_value(id).is_none() =>
+            {
+                var
+            }
+            _ => continue,
+        };
+        let mut dependencies = var.dependencies();
+        dependencies.sort();
+        dependencies.dedup();
+
+        let candidates: Option<Vec<(Identifier, Vec<Choice>)>> = dependencies
+            .into_iter()
+            .map(|dep| static_choices(vars, defaults, dep))
+            .collect();
+        let candidates = match candidates {
+            Some(candidates) => candidates,
+            None => continue,
+        };
+
+        for combination in combinations(&candidates) {
+            if let Ok(command) = var.substitute_for_choices(&combination) {
+                commands.push((var.name(), command));
+            }
+        }
+    }
+    commands
+}
+
+fn static_choices(
+    vars: &dyn VarsCollection,
+    defaults: &dyn VarsDefaultValues,
+    id: Identifier,
+) -> Option<(Identifier, Vec<Choice>)> {
+    // the command of a var that depends on a secret var is skipped by the caller.
+    if let Some(default) = defaults.default_value(&id) {
+        let default = default.to_owned();
+        return Some((id, vec![default]));
+    }
+    vars.get(&id)
+        .filter(|var| !var.is_command() && !var.is_input())
+        .map(|var| var.choices())
+        .map(|choices| (id, choices))
+}
+
+fn combinations(candidates: &[(Identifier, Vec<Choice>)]) -> Vec<HashMap<Identifier, Choice>> {
+    candidates
+        .iter()
+        .fold(vec![HashMap::new()], |acc, (id, choices)| {
+            acc.into_iter()
+                .flat_map(|combination| {
+                    choices.iter().map(move |choice| {
+                        let mut combination = combination.clone();
+                        combination.insert(id.clone(), choice.clone());
+                        combination
+                    })
+                })
+                .collect()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::warmup_commands;
+    use crate::algorithms::execution_sequence_for_dependencies;
+    use crate::algorithms::mocks::{VarsCollectionMock, VarsDefaultValuesMock};
+    use crate::entities::aliases::Alias;
+    use crate::entities::choices::Choice;
+    use crate::entities::identifiers::fixtures::*;
+    use crate::entities::vars::fixtures::*;
+    use maplit::hashmap;
+
+    #[test]
+    fn test_warmup_commands() {
+        let full = vec![
+            VAR_DIRECTORY.clone(),
+            VAR_LISTING.clone(),
+            VAR_PATTERN.clone(),
+            VAR_USE_LISTING.clone(),
+        ];
+        let repo = VarsCollectionMock(full.into_iter().map(|c| (c.name(), c)).collect());
+        let defaults = VarsDefaultValuesMock::default();
+        let alias = Alias::new("alias", "desc", "echo {{ use_listing }}");
+        let seq = execution_sequence_for_dependencies(&repo, alias).unwrap();
+        let mut commands = warmup_commands(&repo, &defaults, &seq);
+        commands.sort();
+        // listing depends on two static vars with two choices each,
+        // use_listing depends on listing which is not static.
+        assert_eq!(commands.len(), 4);
+        assert!(commands
+            .iter()
+            .all(|(id, _)| id == &VAR_LISTING_NAME.clone()));
+        assert!(commands.contains(&(
+            VAR_LISTING_NAME.clone(),
+            String::from("ls -l /home |grep -v ryad")
+        )));
+
+        let defaults = VarsDefaultValuesMock(hashmap! {
+            VAR_DIRECTORY_NAME.clone() => Choice::from_value("/tmp"),
+            VAR_LISTING_NAME.clone() => Choice::from_value("some_file"),
+        });
+        let mut commands = warmup_commands(&repo, &defaults, &seq);
+        commands.sort();
+        assert_eq!(
+            commands,
+            vec![
+                (
+                    VAR_USE_LISTING_NAME.clone(),
+                    String::from("cat some_file |grep -v ryad")
+                ),
+                (
+                    VAR_USE_LISTING_NAME.clone(),
+                    String::from("cat some_file |grep -v service")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_warmup_skips_commands_depending_on_secrets() {
+        let full = vec![
+            VAR_DIRECTORY.clone().with_secret(true),
+            VAR_LISTING.clone(),
+            VAR_PATTERN.clone(),
+            VAR_USE_LISTING.clone(),
+        ];
+        let repo = VarsCollectionMock(full.into_iter().map(|c| (c.name(), c)).collect());
+        let defaults = VarsDefaultValuesMock(hashmap! {
+            VAR_DIRECTORY_NAME.clone() => Choice::from_value("/tmp"),
+        });
+        let alias = Alias::new("alias", "desc", "echo {{ use_listing }}");
+        let seq = execution_sequence_for_dependencies(&repo, alias).unwrap();
+        assert!(warmup_commands(&repo, &defaults, &seq).is_empty());
+    }
+}
@@ -1,4 +1,5 @@
 use crate::entities::aliases::{Alias, ResolvedAlias};
+use crate::entities::history::{ExecutionOutcome, HistoryEntry};
 use std::fmt::Display;
 use std::{cell::RefCell, collections::HashMap};
 
@@ -14,6 +15,7 @@ impl SamLogger for SilentLogger {
 #[derive(Default)]
 pub struct LogExecutor {
     pub commands: RefCell<Vec<(ResolvedAlias, HashMap<String, String>)>>,
+    pub prints: bool,
 }
 
 impl SamExecutor for LogExecutor {
@@ -26,27 +28,35 @@ impl SamExecutor for LogExecutor {
         cmd_mut.push((alias.clone(), env_variables.to_owned()));
         Ok(0)
     }
+
+    fn prints_command(&self) -> bool {
+        self.prints
+    }
 }
 
 #[derive(Default)]
 pub struct InMemoryHistory {
-    pub aliases: RefCell<std::collections::VecDeque<ResolvedAlias>>,
+    pub entries: RefCell<std::collections::VecDeque<HistoryEntry>>,
 }
 
 impl SamHistory for InMemoryHistory {
     fn put(&mut self, alias: ResolvedAlias) -> Result<(), ErrorSamEngine> {
-        let mut queue = self.aliases.borrow_mut();
-        queue.push_front(alias);
+        let mut queue = self.entries.borrow_mut();
+        queue.push_back(HistoryEntry::new(alias, "/"));
+        Ok(())
+    }
+
+    fn set_last_outcome(&mut self, outcome: ExecutionOutcome) -> Result<(), ErrorSamEngine> {
+        let mut queue = self.entries.borrow_mut();
+        if let Some(last) = queue.pop_back() {
+            queue.push_back(last.with_outcome(&outcome));
+        }
         Ok(())
     }
 
-    fn get_last_n(&self, n: usize) -> Result<Vec<ResolvedAlias>, ErrorSamEngine> {
-        Ok(self
-            .aliases
-            .borrow()
-            .iter()
-            .take(n)
-            .map(ToOwned::to_owned)
-            .collect())
+    fn get_last_n_entries(&self, n: usize) -> Result<Vec<HistoryEntry>, ErrorSamEngine> {
+        let queue = self.entries.borrow();
+        let skip = queue.len().saturating_sub(n);
+        Ok(queue.iter().skip(skip).map(ToOwned::to_owned).collect())
     }
 }
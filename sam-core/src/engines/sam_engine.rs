@@ -6,12 +6,14 @@ use crate::entities::aliases::{Alias, ResolvedAlias};
 use crate::entities::choices::Choice;
 use crate::entities::commands::Command;
 use crate::entities::dependencies::{ErrorsResolver, Resolver};
+use crate::entities::history::{ExecutionOutcome, HistoryEntry};
 use crate::entities::identifiers::Identifier;
 use std::cell::RefCell;
 // TODO get rid of this import
 use std::collections::HashMap;
 use std::fmt::Display;
 use std::rc::Rc;
+use std::time::Instant;
 use thiserror::Error;
 
 const PROMPT: &str = "Choose an alias to run > ";
@@ -49,7 +51,7 @@ pub enum ErrorsAliasCollection {
 // Rename SamCommand -> UseCaseAliasExec
 //
 // Exclude
-// -> DisplayHistory,           DisplayLastExecutedAlias   from here and move it to the UseCaseAudit
+// -> DisplayLastExecutedAlias   from here and move it to the UseCaseAudit
 //
 // Exclude
 // -> ExecuteLastExecutedAlias, ModifyThenExecuteLastAlias from here and move it to the UseCaseExecutionReplay
@@ -61,7 +63,8 @@ pub enum SamCommand {
     DisplayLastExecutedAlias,
     ExecuteLastExecutedAlias,
     ModifyThenExecuteLastAlias,
-    DisplayHistory,
+    ExecuteResolvedAlias { alias: ResolvedAlias },
+    ModifyThenExecuteAlias { alias: ResolvedAlias },
 }
 
 // TODO Rename to UseCaseAliasExec
@@ -97,7 +100,8 @@ impl<
             DisplayLastExecutedAlias => self.display_last_executed_alias(),
             ExecuteLastExecutedAlias => self.execute_last_executed_alias(),
             ModifyThenExecuteLastAlias => self.modify_then_execute_last_executed_alias(),
-            DisplayHistory => self.display_history(),
+            ExecuteResolvedAlias { alias } => self.execute_resolved_alias(&alias),
+            ModifyThenExecuteAlias { alias } => self.modify_then_execute_alias(alias),
         }
     }
 
@@ -111,18 +115,53 @@ impl<
         self.run_alias(alias)
     }
 
-    fn run_alias(&self, alias: &Alias) -> Result<i32> {
+    /// resolves the vars of an alias, the command is neither executed nor recorded in the history.
+    pub fn resolve_alias(&self, alias_id: &Identifier) -> Result<ResolvedAlias> {
+        let alias = self.aliases.get(alias_id)?;
+        self.resolve(alias)
+    }
+
+    fn resolve(&self, alias: &Alias) -> Result<ResolvedAlias> {
         let exec_seq = execution_sequence_for_dependencies(&self.vars, alias)?;
+        let defaults = AliasDefaults {
+            defaults: &self.defaults,
+            presets: alias.defaults(),
+        };
         let choices: HashMap<Identifier, Choice> =
-            choices_for_execution_sequence(&self.vars, &self.defaults, &self.resolver, exec_seq)?
+            choices_for_execution_sequence(&self.vars, &defaults, &self.resolver, exec_seq)?
                 .into_iter()
                 .collect();
 
-        let final_alias = alias.with_choices(&choices).unwrap();
-        self.history.borrow_mut().put(final_alias.clone())?;
-        self.logger.final_command(alias, &final_alias.command());
-        self.executor
-            .execute_resolved_alias(&final_alias, &self.env_variables)
+        let secrets: Vec<Identifier> = choices
+            .keys()
+            .filter(|id| self.vars.is_secret(id))
+            .cloned()
+            .collect();
+        Ok(alias.with_choices(&choices).unwrap().with_secrets(secrets))
+    }
+
+    fn run_alias(&self, alias: &Alias) -> Result<i32> {
+        let final_alias = self.resolve(alias)?;
+        self.execute(alias, &final_alias)
+    }
+
+    // every execution goes through here to be recorded in the history along with its outcome.
+    fn execute(&self, alias: &Alias, final_alias: &ResolvedAlias) -> Result<i32> {
+        self.history.borrow_mut().put(final_alias.redacted())?;
+        self.logger
+            .final_command(alias, &final_alias.redacted_command());
+        let started = Instant::now();
+        let result = self
+            .executor
+            .execute_resolved_alias(final_alias, &self.env_variables);
+        let printed = self.executor.prints_command();
+        let outcome = ExecutionOutcome {
+            exit_code: result.as_ref().ok().copied().filter(|_| !printed),
+            duration: started.elapsed(),
+            printed,
+        };
+        self.history.borrow_mut().set_last_outcome(outcome)?;
+        result
     }
 
     fn display_last_executed_alias(&self) -> Result<i32> {
@@ -134,64 +173,95 @@ impl<
         Ok(0)
     }
 
-    fn display_history(&self) -> Result<i32> {
-        let resolved_alias_o = self.history.borrow().get_last_n(10)?;
-        for alias in resolved_alias_o {
-            println!("\n=============\n");
-            print!("{}", alias);
-            print!("\n=============\n");
-        }
-        Ok(0)
-    }
-
     fn modify_then_execute_last_executed_alias(&mut self) -> Result<i32> {
         let resolved_alias_o = self.history.borrow().get_last()?;
         if let Some(resolved_alias) = resolved_alias_o {
-            let original_alias = Alias::from(resolved_alias.clone());
-            let exec_seq = execution_sequence_for_dependencies(&self.vars, original_alias.clone())?;
-            let identifiers = exec_seq.identifiers();
-            if !identifiers.is_empty() {
-                let selected_var = self.resolver.select_identifier(
-                    &identifiers,
-                    None,
-                    "Select the variable to override:",
-                )?;
-
-                let var_position = identifiers
-                    .iter()
-                    .position(|x| x == &selected_var)
-                    .unwrap_or_default();
-
-                let new_defaults: HashMap<Identifier, Choice> = identifiers
-                    .into_iter()
-                    .skip(var_position + 1)
-                    .flat_map(|e| resolved_alias.choice(&e).map(|choice| (e, choice)))
-                    .collect();
-
-                self.defaults.set_defaults(&new_defaults);
-            }
-            self.execute_alias(&original_alias.identifier())
+            self.modify_then_execute_alias(resolved_alias)
         } else {
             println!("history empty");
             Ok(0)
         }
     }
 
-    fn execute_last_executed_alias(&self) -> Result<i32> {
+    fn modify_then_execute_alias(&mut self, resolved_alias: ResolvedAlias) -> Result<i32> {
+        let original_alias = Alias::from(resolved_alias.clone());
+        let exec_seq = execution_sequence_for_dependencies(&self.vars, original_alias.clone())?;
+        let identifiers = exec_seq.identifiers();
+        if !identifiers.is_empty() {
+            let selected_var = self.resolver.select_identifier(
+                &identifiers,
+                None,
+                "Select the variable to override:",
+            )?;
+
+            let var_position = identifiers
+                .iter()
+                .position(|x| x == &selected_var)
+                .unwrap_or_default();
+
+            let new_defaults: HashMap<Identifier, Choice> = identifiers
+                .into_iter()
+                .skip(var_position + 1)
+                .filter(|e| !resolved_alias.is_secret(e))
+                .flat_map(|e| resolved_alias.choice(&e).map(|choice| (e, choice)))
+                .collect();
+
+            self.defaults.set_defaults(&new_defaults);
+        }
+        self.execute_alias(&original_alias.identifier())
+    }
+
+    fn execute_last_executed_alias(&mut self) -> Result<i32> {
         let resolved_alias_o = self.history.borrow().get_last()?;
         if let Some(alias) = resolved_alias_o {
-            self.executor
-                .execute_resolved_alias(&alias, &self.env_variables)
+            self.execute_resolved_alias(&alias)
         } else {
             println!("history empty");
             Ok(0)
         }
     }
+
+    // the choices of secret vars are never stored, the user is prompted for them again.
+    fn execute_resolved_alias(&mut self, alias: &ResolvedAlias) -> Result<i32> {
+        if alias.has_secrets() {
+            let defaults: HashMap<Identifier, Choice> = alias
+                .choices()
+                .iter()
+                .filter(|(id, _)| !alias.is_secret(id))
+                .map(|(id, choice)| (id.clone(), choice.clone()))
+                .collect();
+            self.defaults.set_defaults(&defaults);
+            return self.execute_alias(alias.name());
+        }
+        self.execute(&Alias::from(alias.clone()), alias)
+    }
+}
+
+// AliasDefaults falls back on the presets of an alias
+// for the vars that don't already have a default value.
+struct AliasDefaults<'a> {
+    defaults: &'a dyn VarsDefaultValues,
+    presets: HashMap<Identifier, Choice>,
+}
+
+impl VarsDefaultValues for AliasDefaults<'_> {
+    fn default_value(&self, id: &Identifier) -> Option<&Choice> {
+        self.defaults
+            .default_value(id)
+            .or_else(|| self.presets.get(id))
+    }
 }
 
 pub trait SamHistory {
     fn put(&mut self, alias: ResolvedAlias) -> Result<()>;
-    fn get_last_n(&self, n: usize) -> Result<Vec<ResolvedAlias>>;
+    /// records the outcome of the execution of the alias that was last `put` by this instance.
+    fn set_last_outcome(&mut self, outcome: ExecutionOutcome) -> Result<()>;
+    /// returns the last n entries, from the oldest to the most recent one.
+    fn get_last_n_entries(&self, n: usize) -> Result<Vec<HistoryEntry>>;
+    fn get_last_n(&self, n: usize) -> Result<Vec<ResolvedAlias>> {
+        let entries = self.get_last_n_entries(n)?;
+        Ok(entries.into_iter().map(|e| e.alias).collect())
+    }
     fn get_last(&self) -> Result<Option<ResolvedAlias>> {
         let mut last = self.get_last_n(1)?;
         Ok(last.pop())
@@ -211,6 +281,10 @@ pub trait SamExecutor {
         alias: &ResolvedAlias,
         env_variables: &HashMap<String, String>,
     ) -> Result<i32>;
+    /// true when the command is handed back to the user instead of being executed.
+    fn prints_command(&self) -> bool {
+        false
+    }
 }
 
 pub type Result<T> = std::result::Result<T, ErrorSamEngine>;
@@ -223,8 +297,9 @@ pub enum ErrorSamEngine {
     InvalidAliasSelection,
     #[error("could not resolve the dependency because\n-> {0}")]
     Resolver(#[from] ErrorsResolver),
+    // boxed, it is by far the largest variant.
     #[error("could not figure out dependencies\n-> {0}")]
-    DependencyResolution(#[from] ErrorDependencyResolution),
+    DependencyResolution(Box<ErrorDependencyResolution>),
     #[error("could not select the alias to run\n-> {0}")]
     AliasRepositoryT(#[from] ErrorsAliasCollection),
     #[error("could not run a command\n-> {0}")]
@@ -233,6 +308,30 @@ pub enum ErrorSamEngine {
     HistoryNotAvailable(#[from] Box<dyn std::error::Error>),
 }
 
+impl From<ErrorDependencyResolution> for ErrorSamEngine {
+    fn from(err: ErrorDependencyResolution) -> Self {
+        ErrorSamEngine::DependencyResolution(Box::new(err))
+    }
+}
+
+impl ErrorSamEngine {
+    /// true when a var could not be resolved because sam isn't allowed to prompt for it.
+    pub fn requires_interaction(&self) -> bool {
+        use ErrorDependencyResolution::*;
+        match self {
+            ErrorSamEngine::Resolver(ErrorsResolver::InteractionRequired(_)) => true,
+            ErrorSamEngine::DependencyResolution(err) => match err.as_ref() {
+                UnresolvedVars(_) => true,
+                NoChoiceForVar { error, .. } => {
+                    matches!(error, ErrorsResolver::InteractionRequired(_))
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::cell::RefCell;
@@ -315,6 +414,16 @@ mod tests {
             })
             .expect("Should not return an error");
         let resolved_aliases = executor.commands.borrow();
+        let last_entry = engine
+            .history
+            .borrow()
+            .get_last_n_entries(1)
+            .unwrap()
+            .pop()
+            .expect("the execution should be in the history");
+        assert_eq!(last_entry.exit_code, Some(0));
+        assert!(last_entry.duration().is_some());
+        assert!(!last_entry.printed);
 
         // Only one alias was executed
         assert_eq!(resolved_aliases.len(), 1);
@@ -329,6 +438,121 @@ mod tests {
         );
     }
 
+    #[test]
+    fn secret_vars_are_not_stored() {
+        let variable_1 = Identifier::new("variable_1");
+        let variable_5 = Identifier::new("variable_5");
+        let static_res = hashmap! {
+            variable_1.clone() => Choice::new("value 1", None),
+            variable_5.clone() => Choice::new("s3cr3t", None),
+        };
+        let executor = Rc::new(LogExecutor::default());
+        let mut engine = make_engine(HashMap::new(), static_res, None, executor.clone());
+        engine
+            .run(SamCommand::ExecuteAlias {
+                alias: Identifier::new("alias_3"),
+            })
+            .expect("Should not return an error");
+        let stored = engine.history.borrow().get_last().unwrap().unwrap();
+        {
+            let executed = executor.commands.borrow();
+            let (executed, _env_vars) = executed.first().unwrap();
+            assert_eq!(
+                executed.resolved_alias(),
+                "login --user value 1 --token s3cr3t"
+            );
+            assert_eq!(stored, executed.redacted());
+        }
+        assert!(!stored.resolved_alias().contains("s3cr3t"));
+        assert_eq!(stored.choice(&variable_5), Some(Choice::redacted()));
+
+        // replaying the alias prompts for the secret again rather than running the stored value
+        engine
+            .run(SamCommand::ExecuteResolvedAlias { alias: stored })
+            .expect("Should not return an error");
+        let executed = executor.commands.borrow();
+        assert_eq!(executed.len(), 2);
+        assert_eq!(
+            executed[1].0.resolved_alias(),
+            "login --user value 1 --token s3cr3t"
+        );
+    }
+
+    #[test]
+    fn printed_aliases_are_not_executed() {
+        let static_res = hashmap! {
+            Identifier::new("variable_1") => Choice::new("value_1", None),
+        };
+        let dynamic_res = hashmap! {
+            String::from("echo '$SOME_ENV_VAR\\ntoto'") => Choice::new("toto", None)
+        };
+        let executor = Rc::new(LogExecutor {
+            prints: true,
+            ..LogExecutor::default()
+        });
+        let mut engine = make_engine(dynamic_res, static_res, None, executor.clone());
+        engine
+            .run(SamCommand::ExecuteAlias {
+                alias: Identifier::new("alias_2"),
+            })
+            .expect("Should not return an error");
+        assert_eq!(executor.commands.borrow().len(), 1);
+        let last_entry = engine
+            .history
+            .borrow()
+            .get_last_n_entries(1)
+            .unwrap()
+            .pop()
+            .expect("the printed alias should be in the history");
+        assert!(last_entry.printed);
+        assert_eq!(last_entry.exit_code, None);
+    }
+
+    #[test]
+    fn replayed_aliases_are_recorded() {
+        let static_res = hashmap! {
+            Identifier::new("variable_1") => Choice::new("value_1", None),
+        };
+        let dynamic_res = hashmap! {
+            String::from("echo '$SOME_ENV_VAR\\ntoto'") => Choice::new("toto", None)
+        };
+        let executor = Rc::new(LogExecutor::default());
+        let mut engine = make_engine(dynamic_res, static_res, None, executor.clone());
+        engine
+            .run(SamCommand::ExecuteAlias {
+                alias: Identifier::new("alias_2"),
+            })
+            .expect("Should not return an error");
+        engine
+            .run(SamCommand::ExecuteLastExecutedAlias)
+            .expect("Should not return an error");
+        let executed = executor.commands.borrow();
+        assert_eq!(executed.len(), 2);
+        let entries = engine.history.borrow().get_last_n_entries(2).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(&entries[1].alias, &executed[1].0);
+        assert_eq!(entries[1].exit_code, Some(0));
+        assert!(entries[1].duration().is_some());
+    }
+
+    #[test]
+    fn resolve_alias() {
+        let static_res = hashmap! {
+            Identifier::new("variable_1") => Choice::new("value_1", None),
+        };
+        let dynamic_res = hashmap! {
+            String::from("echo '$SOME_ENV_VAR\\ntoto'") => Choice::new("toto", None)
+        };
+        let executor = Rc::new(LogExecutor::default());
+        let engine = make_engine(dynamic_res, static_res, None, executor.clone());
+        let resolved = engine
+            .resolve_alias(&Identifier::new("alias_2"))
+            .expect("Should not return an error");
+        assert_eq!(resolved.choices().len(), 2);
+        assert!(executor.commands.borrow().is_empty());
+        assert!(engine.history.borrow().get_last().unwrap().is_none());
+    }
+
     fn make_engine(
         dynamic_res: HashMap<String, Choice>,
         static_res: HashMap<Identifier, Choice>,
@@ -360,6 +584,7 @@ mod mocks {
     use crate::entities::aliases::Alias;
     use crate::entities::aliases::ResolvedAlias;
     use crate::entities::choices::Choice;
+    use crate::entities::history::{ExecutionOutcome, HistoryEntry};
     use crate::entities::identifiers::Identifier;
     use std::cell::RefCell;
     use std::collections::HashMap;
@@ -377,24 +602,28 @@ mod mocks {
 
     #[derive(Default)]
     pub struct InMemoryHistory {
-        pub aliases: RefCell<VecDeque<ResolvedAlias>>,
+        pub entries: RefCell<VecDeque<HistoryEntry>>,
     }
 
     impl SamHistory for InMemoryHistory {
         fn put(&mut self, alias: ResolvedAlias) -> super::Result<()> {
-            let mut queue = self.aliases.borrow_mut();
-            queue.push_front(alias);
+            let mut queue = self.entries.borrow_mut();
+            queue.push_back(HistoryEntry::new(alias, "/"));
             Ok(())
         }
 
-        fn get_last_n(&self, n: usize) -> super::Result<Vec<ResolvedAlias>> {
-            Ok(self
-                .aliases
-                .borrow()
-                .iter()
-                .take(n)
-                .map(ToOwned::to_owned)
-                .collect())
+        fn set_last_outcome(&mut self, outcome: ExecutionOutcome) -> super::Result<()> {
+            let mut queue = self.entries.borrow_mut();
+            if let Some(last) = queue.pop_back() {
+                queue.push_back(last.with_outcome(&outcome));
+            }
+            Ok(())
+        }
+
+        fn get_last_n_entries(&self, n: usize) -> super::Result<Vec<HistoryEntry>> {
+            let queue = self.entries.borrow();
+            let skip = queue.len().saturating_sub(n);
+            Ok(queue.iter().skip(skip).map(ToOwned::to_owned).collect())
         }
     }
 
@@ -475,7 +704,11 @@ mod fixtures {
                 desc: val1 description ns2
             - name: 'variable_4'
               desc: description_ns2_v2'
-              from_input: prompt";
+              from_input: prompt
+            - name: 'variable_5'
+              desc: 'a token'
+              from_input: token
+              secret: true";
 
         let alias_str = "
             - name: 'alias_1'
@@ -483,7 +716,10 @@ mod fixtures {
               alias: 'some_cmd --type=$SOME_ENV_VAR_2 {{variable_1}}|grep {{variable_2}}'
             - name: 'alias_2'
               desc: 'description of alias_1 in ns2'
-              alias: 'some_cmd --type=$SOME_ENV_VAR_2 {{variable_1}}|grep {{variable_2}} | echo {{variable_1}} '";
+              alias: 'some_cmd --type=$SOME_ENV_VAR_2 {{variable_1}}|grep {{variable_2}} | echo {{variable_1}} '
+            - name: 'alias_3'
+              desc: 'an alias with a secret'
+              alias: 'login --user {{variable_1}} --token {{variable_5}}'";
 
         let env_variables = hashmap! {
             "SOME_ENV_VAR".to_string() => "env_var_value".to_string(),
@@ -1,4 +1,4 @@
-use crate::entities::choices::Choice;
+use crate::entities::choices::{Choice, REDACTED};
 use crate::entities::commands::Command;
 use crate::entities::dependencies::Dependencies;
 use crate::entities::dependencies::ErrorsResolver;
@@ -10,7 +10,7 @@ use lazy_static::lazy_static;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt::Display;
 use std::fmt::Formatter;
 
@@ -23,11 +23,42 @@ lazy_static! {
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(try_from = "AliasDefinition")]
 pub struct Alias {
     #[serde(flatten)]
     name: Identifier,
     desc: String,
     alias: String,
+    // preset choices, keyed by var identifier, used unless a choice was provided otherwise.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    defaults: BTreeMap<String, String>,
+}
+
+// AliasDefinition is what an alias looks like in a definition file,
+// unknown fields are rejected so that typos don't go unnoticed.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AliasDefinition {
+    name: String,
+    namespace: Option<String>,
+    desc: String,
+    alias: String,
+    #[serde(default)]
+    defaults: BTreeMap<String, String>,
+}
+
+impl From<AliasDefinition> for Alias {
+    fn from(definition: AliasDefinition) -> Self {
+        Alias {
+            name: Identifier {
+                inner: definition.name,
+                namespace: definition.namespace,
+            },
+            desc: definition.desc,
+            alias: definition.alias,
+            defaults: definition.defaults,
+        }
+    }
 }
 
 impl Alias {
@@ -39,9 +70,34 @@ impl Alias {
             name: Identifier::new(name),
             desc: description.into(),
             alias: alias.into(),
+            defaults: BTreeMap::new(),
         }
     }
 
+    pub fn with_defaults(mut self, defaults: BTreeMap<String, String>) -> Alias {
+        self.defaults = defaults;
+        self
+    }
+
+    pub fn raw_defaults(&self) -> &BTreeMap<String, String> {
+        &self.defaults
+    }
+
+    /// the preset choices of the alias, vars without a namespace belong to the alias' namespace.
+    pub fn defaults(&self) -> HashMap<Identifier, Choice> {
+        self.defaults
+            .iter()
+            .map(|(var, value)| {
+                let (name, namespace) = Identifier::maybe_namespace(var.as_str());
+                let namespace = namespace.or_else(|| self.namespace().map(ToString::to_string));
+                (
+                    Identifier::with_namespace(name, namespace),
+                    Choice::from_value(value.as_str()),
+                )
+            })
+            .collect()
+    }
+
     pub fn update(&mut self, alias: String) {
         self.alias = alias;
     }
@@ -69,6 +125,7 @@ impl Alias {
             original_alias: self.alias.clone(),
             resolved_alias: res,
             choices: choices.clone(),
+            secrets: BTreeSet::new(),
         })
     }
 
@@ -79,6 +136,7 @@ impl Alias {
             name: self.name.clone(),
             desc: self.desc.clone(),
             alias: res,
+            defaults: self.defaults.clone(),
         }
     }
 
@@ -147,6 +205,9 @@ pub struct ResolvedAlias {
     original_alias: String,
     resolved_alias: String,
     choices: HashMap<Identifier, Choice>,
+    // the vars whose choices must not be displayed nor stored.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    secrets: BTreeSet<Identifier>,
 }
 
 impl ResolvedAlias {
@@ -163,6 +224,56 @@ impl ResolvedAlias {
             original_alias,
             resolved_alias,
             choices,
+            secrets: BTreeSet::new(),
+        }
+    }
+
+    pub fn with_secrets(mut self, secrets: impl IntoIterator<Item = Identifier>) -> Self {
+        self.secrets = secrets.into_iter().collect();
+        self
+    }
+
+    pub fn has_secrets(&self) -> bool {
+        !self.secrets.is_empty()
+    }
+
+    pub fn is_secret(&self, identifier: &Identifier) -> bool {
+        self.secrets.contains(identifier)
+    }
+
+    /// a copy of the alias where the choices of the secret vars are masked,
+    /// it is what gets displayed or written to the disk.
+    pub fn redacted(&self) -> ResolvedAlias {
+        if !self.has_secrets() {
+            return self.clone();
+        }
+        let choices: HashMap<Identifier, Choice> = self
+            .choices
+            .iter()
+            .map(|(id, choice)| {
+                if self.is_secret(id) {
+                    (id.clone(), Choice::redacted())
+                } else {
+                    (id.clone(), choice.clone())
+                }
+            })
+            .collect();
+        let resolved_alias = Alias::from(self.clone())
+            .substitute_for_choices(&choices)
+            .unwrap_or_else(|_| REDACTED.to_string());
+        ResolvedAlias {
+            resolved_alias,
+            choices,
+            ..self.clone()
+        }
+    }
+
+    /// the resolved command with the choices of the secret vars masked.
+    pub fn redacted_command(&self) -> String {
+        if self.has_secrets() {
+            self.redacted().resolved_alias
+        } else {
+            self.resolved_alias.clone()
         }
     }
     pub fn choice(&self, identifier: &Identifier) -> Option<Choice> {
@@ -194,6 +305,7 @@ impl From<ResolvedAlias> for Alias {
             name: r_alias.name,
             desc: r_alias.desc,
             alias: r_alias.original_alias,
+            defaults: BTreeMap::new(),
         }
     }
 }
@@ -239,7 +351,8 @@ impl Display for ResolvedAlias {
             termion::style::Bold,
             termion::style::Reset,
         )?;
-        for (choice, value) in &self.choices {
+        let redacted = self.redacted();
+        for (choice, value) in &redacted.choices {
             writeln!(
                 f,
                 "\t{}{}{} =\t{}",
@@ -256,7 +369,7 @@ impl Display for ResolvedAlias {
             termion::style::Bold,
             termion::style::Italic,
             termion::style::Reset,
-            self.resolved_alias
+            redacted.resolved_alias
         )
     }
 }
@@ -276,9 +389,16 @@ impl Into<ShellCommand<String>> for Alias {
     }
 }
 
+// a bash or zsh alias definition.
 impl Display for Alias {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "alias {}='{}' # {}", self.name, self.alias, self.desc)
+        writeln!(
+            f,
+            "alias {}='{}' # {}",
+            self.name,
+            self.alias.replace('\'', "'\\''"),
+            self.desc.lines().collect::<Vec<&str>>().join(" ")
+        )
     }
 }
 
@@ -286,31 +406,37 @@ pub mod fixtures {
     use crate::entities::aliases::Alias;
     use crate::entities::identifiers::fixtures::*;
     use lazy_static::lazy_static;
+    use std::collections::BTreeMap;
 
     lazy_static! {
         pub static ref ALIAS_LS_DIR: Alias = Alias {
             name: ALIAS_LS_DIR_NAME.clone(),
             desc: String::from("some desc"),
             alias: String::from("ls {{ directory }}"),
+            defaults: BTreeMap::new(),
         };
         pub static ref ALIAS_GREP_DIR: Alias = Alias {
             name: ALIAS_GREP_DIR_NAME.clone(),
             desc: String::from("some desc"),
             alias: String::from("[[ dirs::list ]]|grep {{ pattern }}"),
+            defaults: BTreeMap::new(),
         };
         pub static ref ALIAS_GREP_DIR_NO_NS: Alias = Alias {
             name: ALIAS_GREP_DIR_NAME.clone(),
             desc: String::from("some desc"),
             alias: String::from("[[ list ]]| grep {{ pattern }}"),
+            defaults: BTreeMap::new(),
         };
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Alias;
+    use super::{Alias, ResolvedAlias};
+    use crate::entities::choices::{Choice, REDACTED};
     use crate::entities::commands::Command;
     use crate::entities::identifiers::Identifier;
+    use crate::entities::namespaces::NamespaceUpdater;
     #[test]
     fn vars() {
         let alias = Alias::new(
@@ -328,6 +454,57 @@ mod tests {
         assert_eq!(expected_vars, vars);
     }
 
+    #[test]
+    fn defaults() {
+        let mut alias = Alias::new("alias", "desc", "kubectl --context {{ cluster }} get pods");
+        NamespaceUpdater::update(&mut alias, "k8s");
+        let alias = alias.with_defaults(maplit::btreemap! {
+            String::from("cluster") => String::from("prod"),
+            String::from("other::var") => String::from("value"),
+        });
+        let defaults = alias.defaults();
+        assert_eq!(
+            defaults.get(&Identifier::with_namespace("cluster", Some("k8s"))),
+            Some(&Choice::from_value("prod"))
+        );
+        assert_eq!(
+            defaults.get(&Identifier::with_namespace("var", Some("other"))),
+            Some(&Choice::from_value("value"))
+        );
+    }
+
+    #[test]
+    fn redacted() {
+        let alias = Alias::new(
+            "login",
+            "desc",
+            "login --user {{ user }} --token {{ token }}",
+        );
+        let user = Identifier::new("user");
+        let token = Identifier::new("token");
+        let choices = maplit::hashmap! {
+            user.clone() => Choice::from_value("alice"),
+            token.clone() => Choice::from_value("s3cr3t"),
+        };
+        let resolved: ResolvedAlias = alias
+            .with_choices(&choices)
+            .expect("all the vars have a choice")
+            .with_secrets(vec![token.clone()]);
+        assert_eq!(
+            resolved.resolved_alias(),
+            "login --user alice --token s3cr3t"
+        );
+        assert_eq!(
+            resolved.redacted_command(),
+            format!("login --user alice --token {}", REDACTED)
+        );
+        let redacted = resolved.redacted();
+        assert_eq!(redacted.choice(&user), Some(Choice::from_value("alice")));
+        assert_eq!(redacted.choice(&token), Some(Choice::redacted()));
+        assert!(redacted.is_secret(&token));
+        assert!(!resolved.to_string().contains("s3cr3t"));
+    }
+
     #[test]
     fn sanitize() {
         let output = Alias::sanitize("{{ super }} no {{ ns::toto }}", "sup");
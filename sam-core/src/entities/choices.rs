@@ -1,6 +1,11 @@
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
+
+/// what is displayed, or stored, in place of the value of a secret var.
+pub const REDACTED: &str = "******";
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, PartialOrd, Ord, Eq)]
+#[serde(deny_unknown_fields)]
 pub struct Choice {
     value: String,
     desc: Option<String>,
@@ -25,6 +30,9 @@ impl Choice {
             desc: None,
         }
     }
+    pub fn redacted() -> Choice {
+        Choice::from_value(REDACTED)
+    }
     pub fn value(&'_ self) -> &'_ str {
         self.value.as_str()
     }
@@ -2,7 +2,7 @@ use crate::entities::choices::Choice;
 use crate::entities::commands::Command;
 use crate::entities::identifiers::Identifier;
 use crate::entities::processes::ShellCommand;
-use regex::Regex;
+use regex::{NoExpand, Regex};
 use std::collections::HashMap;
 use std::error;
 use thiserror::Error;
@@ -43,8 +43,9 @@ fn substitute_choice(origin: &str, dependency: &Identifier, choice: &str) -> Str
     );
     let re: Regex = Regex::new(re_fmt.as_str()).unwrap();
     let re2: Regex = Regex::new(re2_fmt.as_str()).unwrap();
-    let tmp = re.replace(origin, choice).to_string();
-    re2.replace(&tmp, choice).to_string()
+    // `$` in a choice is not a reference to a capture group.
+    let tmp = re.replace(origin, NoExpand(choice)).to_string();
+    re2.replace(&tmp, NoExpand(choice)).to_string()
 }
 
 #[derive(Debug)]
@@ -81,6 +82,18 @@ pub trait Resolver {
     fn resolve_dynamic<CMD>(&self, var: Identifier, cmd: CMD) -> Result<Choice, ErrorsResolver>
     where
         CMD: Into<ShellCommand<String>>;
+    /// same as `resolve_dynamic` but the output of the command is neither read from
+    /// nor written to a cache, it is used for secret vars.
+    fn resolve_dynamic_uncached<CMD>(
+        &self,
+        var: Identifier,
+        cmd: CMD,
+    ) -> Result<Choice, ErrorsResolver>
+    where
+        CMD: Into<ShellCommand<String>>,
+    {
+        self.resolve_dynamic(var, cmd)
+    }
     fn resolve_static(
         &self,
         var: Identifier,
@@ -110,8 +123,10 @@ pub enum ErrorsResolver {
     NoInputWasProvided(Identifier, String),
     #[error("selection empty")]
     IdentifierSelectionEmpty(),
-    #[error("selection invalid.")]
+    #[error("selection invalid.\n-> {0}")]
     IdentifierSelectionInvalid(Box<dyn error::Error>),
+    #[error("var {0} needs a choice but sam can't prompt for it, set `-c {0}=<value>` or `{}`", .0.env_name())]
+    InteractionRequired(Identifier),
 }
 
 pub mod mocks {
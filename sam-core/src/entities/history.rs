@@ -0,0 +1,282 @@
+use crate::entities::aliases::ResolvedAlias;
+use crate::entities::identifiers::Identifier;
+use crate::entities::namespaces::Namespace;
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// HistoryEntry represents one execution of an alias along with the context it ran in.
+// Entries written by older versions of sam only know about the alias and the working directory.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HistoryEntry {
+    #[serde(rename = "r")]
+    pub alias: ResolvedAlias,
+    pub pwd: String,
+    #[serde(default)]
+    pub started_at: Option<u64>,
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub hostname: Option<String>,
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub version: Option<String>,
+    /// the command was handed back to the user, with `--print`, instead of being executed.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub printed: bool,
+}
+
+/// The result of running a resolved alias, fed back to the history once the execution is over.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutionOutcome {
+    pub exit_code: Option<i32>,
+    pub duration: Duration,
+    pub printed: bool,
+}
+
+impl HistoryEntry {
+    /// new creates an entry for an alias that is about to be executed now.
+    pub fn new(alias: ResolvedAlias, pwd: impl Into<String>) -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("can't get system time");
+        HistoryEntry {
+            alias,
+            pwd: pwd.into(),
+            started_at: Some(now.as_secs()),
+            duration_ms: None,
+            exit_code: None,
+            hostname: None,
+            user: None,
+            version: None,
+            printed: false,
+        }
+    }
+
+    pub fn with_outcome(mut self, outcome: &ExecutionOutcome) -> Self {
+        self.exit_code = outcome.exit_code;
+        self.duration_ms = Some(outcome.duration.as_millis() as u64);
+        self.printed = outcome.printed;
+        self
+    }
+
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration_ms.map(Duration::from_millis)
+    }
+
+    pub fn failed(&self) -> bool {
+        matches!(self.exit_code, Some(code) if code != 0)
+    }
+
+    /// the start time formatted in the local timezone.
+    pub fn started_at_local(&self) -> Option<String> {
+        self.started_at.map(|started_at| {
+            Local
+                .timestamp(started_at as i64, 0)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        })
+    }
+
+    /// a single line, uncolored, description of the entry.
+    pub fn summary(&self) -> String {
+        let exit_code = match self.exit_code {
+            _ if self.printed => String::from("printed"),
+            Some(code) => code.to_string(),
+            None => String::from("-"),
+        };
+        format!(
+            "{}\t{}\t{}\t{}",
+            self.started_at_local()
+                .unwrap_or_else(|| String::from("unknown date")),
+            exit_code,
+            self.alias.name(),
+            self.alias.resolved_alias(),
+        )
+    }
+}
+
+/// HistoryFilter narrows down the history entries, every criteria that is set must match.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HistoryFilter {
+    /// when the identifier has no namespace only the alias name is compared.
+    pub alias: Option<Identifier>,
+    pub namespace: Option<String>,
+    pub pwd: Option<String>,
+    /// unix timestamp, in seconds, of the oldest entry to keep.
+    pub since: Option<u64>,
+    pub failed: bool,
+}
+
+impl HistoryFilter {
+    pub fn matches(&self, entry: &HistoryEntry) -> bool {
+        let alias_matches = match &self.alias {
+            Some(id) if id.namespace.is_some() => entry.alias.name() == id,
+            Some(id) => entry.alias.name().name() == id.name(),
+            None => true,
+        };
+        let namespace_matches = match &self.namespace {
+            Some(ns) => entry.alias.namespace() == Some(ns.as_str()),
+            None => true,
+        };
+        let pwd_matches = match &self.pwd {
+            Some(pwd) => &entry.pwd == pwd,
+            None => true,
+        };
+        let since_matches = match self.since {
+            Some(since) => entry.started_at.map(|t| t >= since).unwrap_or(false),
+            None => true,
+        };
+        alias_matches
+            && namespace_matches
+            && pwd_matches
+            && since_matches
+            && (!self.failed || entry.failed())
+    }
+}
+
+impl Display for HistoryEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        if let Some(when) = self.started_at_local() {
+            write!(
+                f,
+                "{}{}When:{} {}",
+                termion::color::Fg(termion::color::LightCyan),
+                termion::style::Bold,
+                termion::style::Reset,
+                when,
+            )?;
+            if let Some(duration) = self.duration() {
+                write!(f, " (took {:.2}s)", duration.as_secs_f64())?;
+            }
+            writeln!(f)?;
+        }
+        let status = match self.exit_code {
+            _ if self.printed => String::from("printed, not executed"),
+            Some(0) => format!(
+                "{}succeeded{}",
+                termion::color::Fg(termion::color::Green),
+                termion::style::Reset
+            ),
+            Some(code) => format!(
+                "{}failed with exit code {}{}",
+                termion::color::Fg(termion::color::Red),
+                code,
+                termion::style::Reset
+            ),
+            None => String::from("unknown"),
+        };
+        writeln!(
+            f,
+            "{}{}Status:{} {}",
+            termion::color::Fg(termion::color::LightCyan),
+            termion::style::Bold,
+            termion::style::Reset,
+            status,
+        )?;
+        let user = self.user.as_deref().map(|u| format!("{}@", u));
+        let host = self.hostname.as_deref().map(|h| format!("{}:", h));
+        writeln!(
+            f,
+            "{}{}Where:{} {}{}{}",
+            termion::color::Fg(termion::color::LightCyan),
+            termion::style::Bold,
+            termion::style::Reset,
+            user.unwrap_or_default(),
+            host.unwrap_or_default(),
+            self.pwd,
+        )?;
+        if let Some(version) = &self.version {
+            writeln!(
+                f,
+                "{}{}Sam version:{} {}",
+                termion::color::Fg(termion::color::LightCyan),
+                termion::style::Bold,
+                termion::style::Reset,
+                version,
+            )?;
+        }
+        write!(f, "{}", self.alias)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExecutionOutcome, HistoryEntry, HistoryFilter};
+    use crate::entities::aliases::ResolvedAlias;
+    use crate::entities::identifiers::Identifier;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    #[test]
+    fn test_history_entry_with_outcome() {
+        let alias = ResolvedAlias::new(
+            Identifier::with_namespace("alias", Some("ns")),
+            String::from("desc"),
+            String::from("false"),
+            String::from("false"),
+            HashMap::new(),
+        );
+        let entry = HistoryEntry::new(alias, "/tmp");
+        assert!(entry.started_at.is_some());
+        assert!(!entry.failed());
+        let entry = entry.with_outcome(&ExecutionOutcome {
+            exit_code: Some(1),
+            duration: Duration::from_millis(1500),
+            printed: false,
+        });
+        assert!(entry.failed());
+        assert_eq!(entry.duration(), Some(Duration::from_millis(1500)));
+        assert!(entry.summary().contains("\t1\tns::alias\t"));
+
+        let entry = entry.with_outcome(&ExecutionOutcome {
+            exit_code: None,
+            duration: Duration::from_millis(10),
+            printed: true,
+        });
+        assert!(!entry.failed());
+        assert!(entry.summary().contains("\tprinted\tns::alias\t"));
+    }
+
+    #[test]
+    fn test_history_filter() {
+        let alias = ResolvedAlias::new(
+            Identifier::with_namespace("alias", Some("ns")),
+            String::from("desc"),
+            String::from("false"),
+            String::from("false"),
+            HashMap::new(),
+        );
+        let entry = HistoryEntry::new(alias, "/tmp").with_outcome(&ExecutionOutcome {
+            exit_code: Some(0),
+            duration: Duration::from_millis(10),
+            printed: false,
+        });
+        assert!(HistoryFilter::default().matches(&entry));
+        let by_name = HistoryFilter {
+            alias: Some(Identifier::new("alias")),
+            pwd: Some(String::from("/tmp")),
+            ..HistoryFilter::default()
+        };
+        assert!(by_name.matches(&entry));
+        let other_ns = HistoryFilter {
+            alias: Some(Identifier::with_namespace("alias", Some("other"))),
+            ..HistoryFilter::default()
+        };
+        assert!(!other_ns.matches(&entry));
+        let failed = HistoryFilter {
+            failed: true,
+            ..HistoryFilter::default()
+        };
+        assert!(!failed.matches(&entry));
+        let future = HistoryFilter {
+            since: entry.started_at.map(|t| t + 60),
+            ..HistoryFilter::default()
+        };
+        assert!(!future.matches(&entry));
+    }
+}
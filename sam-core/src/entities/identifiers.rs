@@ -96,6 +96,25 @@ impl Identifier {
         self.inner.as_str()
     }
 
+    /// the environment variable that can hold a value for this identifier, `SAM_<NS>_<NAME>`.
+    ///```rust
+    /// use sam_core::entities::identifiers::Identifier;
+    /// let var = Identifier::with_namespace("instance-id", Some("aws"));
+    /// assert_eq!(var.env_name(), "SAM_AWS_INSTANCE_ID");
+    ///```
+    pub fn env_name(&self) -> String {
+        let mut env_name = String::from("SAM");
+        let parts = self.namespace.iter().map(String::as_str);
+        for part in parts.chain(Some(self.name())).filter(|p| !p.is_empty()) {
+            env_name.push('_');
+            env_name.extend(part.chars().map(|c| match c {
+                c if c.is_ascii_alphanumeric() => c.to_ascii_uppercase(),
+                _ => '_',
+            }));
+        }
+        env_name
+    }
+
     #[allow(clippy::should_implement_trait)]
     pub fn from_str(id: &str) -> Identifier {
         let (name, namespace) = Self::maybe_namespace(id);
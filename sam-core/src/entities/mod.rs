@@ -2,6 +2,7 @@ pub mod aliases;
 pub mod choices;
 pub mod commands;
 pub mod dependencies;
+pub mod history;
 pub mod identifiers;
 pub mod namespaces;
 pub mod processes;
@@ -3,14 +3,45 @@ pub trait Namespace {
     fn namespace(&self) -> Option<&str>;
 }
 
+/// the suffix of the stem of files defining a whole namespace, ie `docker.sam.yaml`.
+pub const NAMESPACE_FILE_SUFFIX: &str = ".sam";
+
 pub trait NamespaceUpdater {
     fn update(&mut self, namespace: impl Into<String>);
 
+    /// single-file namespaces are named after the file, others after their directory.
     fn update_from_path(&mut self, path: &Path) -> Option<()> {
-        let namespace = path
-            .parent()
-            .and_then(|e| e.file_name())
-            .and_then(|e| e.to_str());
+        let from_file_stem = path
+            .file_stem()
+            .and_then(|e| e.to_str())
+            .and_then(|e| e.strip_suffix(NAMESPACE_FILE_SUFFIX));
+        let namespace = from_file_stem.or_else(|| {
+            path.parent()
+                .and_then(|e| e.file_name())
+                .and_then(|e| e.to_str())
+        });
         namespace.map(|ns| self.update(ns))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::NamespaceUpdater;
+    use crate::entities::aliases::Alias;
+    use std::path::Path;
+
+    #[test]
+    fn test_update_from_path() {
+        let cases = vec![
+            ("/root/docker/aliases.yaml", "docker"),
+            ("/root/docker/aliases.toml", "docker"),
+            ("/root/k8s.sam.yaml", "k8s"),
+            ("/root/docker/k8s.sam.json", "k8s"),
+        ];
+        for (path, expected) in cases {
+            let mut alias = Alias::new("name", "desc", "alias");
+            alias.update_from_path(Path::new(path));
+            assert_eq!(alias.namespace(), Some(expected));
+        }
+    }
+}
@@ -1,7 +1,11 @@
 use std::collections::HashMap;
 use std::env;
 use std::ffi::OsStr;
-use std::process::Command;
+use std::io::Read;
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, Output, Stdio};
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone)]
 pub struct ShellCommand<T: Clone> {
@@ -49,6 +53,72 @@ impl ShellCommand<String> {
     }
 }
 
+/// runs the provided command and collects its output. the command, along with
+/// the processes it started, gets killed and an `std::io::ErrorKind::TimedOut`
+/// error is returned if it is still running after `timeout`.
+pub fn output_with_timeout(mut command: Command, timeout: Duration) -> std::io::Result<Output> {
+    // the command runs in its own process group so that it can be killed as a whole,
+    // killing the shell alone would leave the commands of a pipeline running.
+    #[cfg(unix)]
+    command.process_group(0);
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdout = child.stdout.take().map(read_to_end_in_background);
+    let stderr = child.stderr.take().map(read_to_end_in_background);
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if started.elapsed() > timeout {
+            kill_process_group(&mut child)?;
+            child.wait()?;
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                format!("command did not finish within {}s", timeout.as_secs()),
+            ));
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+    let collect = |h: Option<std::thread::JoinHandle<Vec<u8>>>| {
+        h.and_then(|h| h.join().ok()).unwrap_or_default()
+    };
+    Ok(Output {
+        status,
+        stdout: collect(stdout),
+        stderr: collect(stderr),
+    })
+}
+
+#[cfg(unix)]
+fn kill_process_group(child: &mut Child) -> std::io::Result<()> {
+    use nix::sys::signal::{killpg, Signal};
+    use nix::unistd::Pid;
+    // the id of the group is the pid of its leader.
+    match killpg(Pid::from_raw(child.id() as i32), Signal::SIGKILL) {
+        Ok(_) => Ok(()),
+        Err(_) => child.kill(),
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(child: &mut Child) -> std::io::Result<()> {
+    child.kill()
+}
+
+fn read_to_end_in_background<R: Read + Send + 'static>(
+    mut reader: R,
+) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buffer = vec![];
+        let _ = reader.read_to_end(&mut buffer);
+        buffer
+    })
+}
+
 impl From<&'_ str> for ShellCommand<String> {
     fn from(s: &'_ str) -> Self {
         Self::new(s.to_string())
@@ -71,3 +141,36 @@ where
         command
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{output_with_timeout, ShellCommand};
+    use std::process::Command;
+    use std::time::Duration;
+
+    #[test]
+    fn test_output_with_timeout() {
+        let cmd: Command = ShellCommand::new("echo toto").into();
+        let output = output_with_timeout(cmd, Duration::from_secs(5)).expect("should succeed");
+        assert_eq!(output.stdout, b"toto\n");
+
+        let cmd: Command = ShellCommand::new("sleep 5").into();
+        let err = output_with_timeout(cmd, Duration::from_millis(100)).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_output_with_timeout_kills_child_processes() {
+        let marker = std::env::temp_dir().join(format!("sam-timeout-{}", std::process::id()));
+        let script = format!("(sleep 0.5; touch {}) & wait", marker.display());
+        let cmd: Command = ShellCommand::new(script).into();
+        let err = output_with_timeout(cmd, Duration::from_millis(100)).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+        std::thread::sleep(Duration::from_secs(1));
+        assert!(
+            !marker.exists(),
+            "the background process should have been killed"
+        );
+    }
+}
@@ -10,6 +10,7 @@ use std::hash::Hash;
 // Var represent a variable with a command that can be used in an crate::core:Alias.
 // Var can be static when choices is not empty or dyamic whenthe from_command is not empty
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[serde(try_from = "VarDefinition")]
 pub struct Var {
     #[serde(flatten)]
     name: Identifier,
@@ -20,6 +21,59 @@ pub struct Var {
     from_command: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none", default)]
     from_input: Option<String>,
+    // the choices of a secret var are never displayed, stored or cached.
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    secret: bool,
+}
+
+// VarDefinition is what a var looks like in a definition file,
+// unknown fields are rejected so that typos don't go unnoticed.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VarDefinition {
+    name: String,
+    namespace: Option<String>,
+    desc: String,
+    #[serde(default)]
+    choices: Vec<Choice>,
+    from_command: Option<String>,
+    from_input: Option<String>,
+    #[serde(default)]
+    secret: bool,
+}
+
+impl TryFrom<VarDefinition> for Var {
+    type Error = String;
+
+    fn try_from(definition: VarDefinition) -> Result<Self, Self::Error> {
+        let sources: Vec<&str> = [
+            ("choices", !definition.choices.is_empty()),
+            ("from_command", definition.from_command.is_some()),
+            ("from_input", definition.from_input.is_some()),
+        ]
+        .iter()
+        .filter(|(_, defined)| *defined)
+        .map(|(source, _)| *source)
+        .collect();
+        if sources.len() > 1 {
+            return Err(format!(
+                "var `{}` can only get its choices from one of `choices`, `from_command` or `from_input`, got `{}`",
+                definition.name,
+                sources.join("` and `")
+            ));
+        }
+        Ok(Var {
+            name: Identifier {
+                inner: definition.name,
+                namespace: definition.namespace,
+            },
+            desc: definition.desc,
+            choices: definition.choices,
+            from_command: definition.from_command,
+            from_input: definition.from_input,
+            secret: definition.secret,
+        })
+    }
 }
 
 impl Var {
@@ -34,6 +88,7 @@ impl Var {
             choices,
             from_command: None,
             from_input: None,
+            secret: false,
         }
     }
 
@@ -49,6 +104,7 @@ impl Var {
             choices: vec![],
             from_command: Some(from_command.into()),
             from_input: None,
+            secret: false,
         }
     }
 
@@ -62,6 +118,7 @@ impl Var {
             choices: vec![],
             from_command: None,
             from_input: Some(from_input.into()),
+            secret: false,
         }
     }
 
@@ -73,10 +130,23 @@ impl Var {
         self.from_input.is_some()
     }
 
+    pub fn is_secret(&self) -> bool {
+        self.secret
+    }
+
+    pub fn with_secret(mut self, secret: bool) -> Var {
+        self.secret = secret;
+        self
+    }
+
     pub fn name(&self) -> Identifier {
         self.name.clone()
     }
 
+    pub fn desc(&self) -> &str {
+        self.desc.as_str()
+    }
+
     pub fn choices(&self) -> Vec<Choice> {
         self.choices.clone()
     }
@@ -209,6 +279,23 @@ mod tests {
             _ => assert!(false),
         }
     }
+
+    #[test]
+    fn test_secret() {
+        let vars: Vec<Var> = serde_yaml::from_str(
+            "
+            - name: token
+              desc: an api token
+              from_input: token
+              secret: true
+            - name: user
+              desc: a user name
+              from_input: user",
+        )
+        .expect("the definitions are valid");
+        assert!(vars[0].is_secret());
+        assert!(!vars[1].is_secret());
+    }
 }
 
 pub mod fixtures {
@@ -230,6 +317,7 @@ pub mod fixtures {
             desc: VAR_USE_LISTING_DESC.clone(),
             choices: VAR_USE_LISTING_CHOICES.clone(),
             from_input: None,
+            secret: false,
         };
         pub static ref VAR_LISTING_COMMAND: String =
             String::from("ls -l {{directory}} |grep -v {{ ns::pattern }}");
@@ -247,6 +335,7 @@ pub mod fixtures {
             desc: VAR_LISTING_DESC.clone(),
             choices: VAR_LISTING_CHOICES.clone(),
             from_input: None,
+            secret: false,
         };
         pub static ref VAR_DIRECTORY_DESC: String =
             String::from("A list of safe directory paths where to perform commands.");
@@ -264,6 +353,7 @@ pub mod fixtures {
             desc: VAR_DIRECTORY_DESC.clone(),
             choices: VAR_DIRECTORY_CHOICES.clone(),
             from_input: None,
+            secret: false,
         };
         pub static ref VAR_PATTERN_DESC: String = String::from("A black list of patterns");
         pub static ref VAR_PATTERN_CHOICE_1: Choice =
@@ -278,6 +368,7 @@ pub mod fixtures {
             desc: VAR_PATTERN_DESC.clone(),
             choices: VAR_PATTERN_CHOICES.clone(),
             from_input: None,
+            secret: false,
         };
         pub static ref VAR_MISSING_COMMAND: String =
             String::from("ls -l {{directory}} |grep -v {{pattern2}}");
@@ -293,6 +384,7 @@ pub mod fixtures {
             desc: VAR_MISSING_DESC.clone(),
             choices: VAR_MISSING_CHOICES.clone(),
             from_input: None,
+            secret: false,
         };
     }
 }
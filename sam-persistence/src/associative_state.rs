@@ -103,6 +103,18 @@ where
         db.save().map_err(ErrorAssociativeState::SaveFailures)
     }
 
+    /// replaces the value of an existing key, the time it was inserted at is kept.
+    pub fn update(&self, key: impl AsRef<str>, value: V) -> Result<(), ErrorAssociativeState> {
+        let db = self.open_db()?;
+        db.write(|db| {
+            if let Some(entry) = db.get_mut(key.as_ref()) {
+                entry.entry = value;
+            }
+        })
+        .map_err(ErrorAssociativeState::WriteFailures)?;
+        db.save().map_err(ErrorAssociativeState::SaveFailures)
+    }
+
     pub fn get(&self, command: impl AsRef<str>) -> Result<Option<V>, ErrorAssociativeState> {
         let db = self.open_db()?;
         let cache_key = command.as_ref();
@@ -128,21 +140,43 @@ where
             .map_err(ErrorAssociativeState::ReadFailure)
     }
 
-    fn open_db(&self) -> Result<FDB<V>, ErrorAssociativeState> {
-        Ok(FDB::<V>::load_from_path(&self.path)
-            .or(FDB::<V>::create_at_path(&self.path, HashMap::default()))
-            .map_err(ErrorAssociativeState::OpenFailure)?)
+    /// returns all the entries, expired ones included, along with the
+    /// unix timestamp at which they were inserted.
+    pub fn timestamped_entries(
+        &self,
+    ) -> Result<impl Iterator<Item = (String, V, u64)>, ErrorAssociativeState> {
+        let db = self.open_db()?;
+        db.read(|db| db.clone().into_iter().map(|(k, v)| (k, v.entry, v.when)))
+            .map_err(ErrorAssociativeState::ReadFailure)
     }
-    fn is_value_valid(&self, c: &StateEntry<V>) -> bool {
+
+    pub fn is_expired(&self, when: u64) -> bool {
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .expect("Can't get system time");
         if let Some(ttl) = self.ttl.as_ref() {
-            c.when + ttl.as_secs() > now.as_secs()
+            when + ttl.as_secs() <= now.as_secs()
         } else {
-            true
+            false
         }
     }
+
+    pub fn path(&self) -> &'_ Path {
+        self.path.as_ref()
+    }
+
+    pub fn interactor<D>(&self, delegate: D) -> AssociativeStateInteractor<V, D> {
+        AssociativeStateInteractor::new(&self.path, self.ttl, delegate)
+    }
+
+    fn open_db(&self) -> Result<FDB<V>, ErrorAssociativeState> {
+        Ok(FDB::<V>::load_from_path(&self.path)
+            .or(FDB::<V>::create_at_path(&self.path, HashMap::default()))
+            .map_err(ErrorAssociativeState::OpenFailure)?)
+    }
+    fn is_value_valid(&self, c: &StateEntry<V>) -> bool {
+        !self.is_expired(c.when)
+    }
 }
 
 pub trait EntrySelector<V> {
@@ -179,7 +213,7 @@ where
     D: EntrySelector<V>,
     V: Value,
 {
-    fn delete_entry(&self) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn delete_entry(&self) -> Result<(), Box<dyn std::error::Error>> {
         let state_iterator = self.entries()?;
         let selected_element = self.delegate.select_entry(state_iterator)?;
         if let Some(key) = selected_element {
@@ -1,18 +1,24 @@
-use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use thiserror::Error;
 
 use sam_core::{
     engines::{ErrorSamEngine, SamHistory},
     entities::aliases::ResolvedAlias,
+    entities::history::{ExecutionOutcome, HistoryEntry},
 };
 
-use crate::sequential_state::{ErrorSequentialState, SequentialState};
+use crate::sequential_state::{ErrorSequentialState, SequentialState, SequentialStateInteractor};
 
 #[derive()]
 pub struct AliasHistory {
     state: SequentialState<HistoryEntry>,
+    path: PathBuf,
+    max_size: Option<usize>,
     pwd: PathBuf,
+    hostname: Option<String>,
+    user: Option<String>,
+    // the entry pushed by this process, kept around to attach the outcome of the execution
+    last_put: Option<HistoryEntry>,
 }
 
 #[derive(Debug, Error)]
@@ -26,29 +32,74 @@ impl AliasHistory {
         path: impl Into<PathBuf>,
         max_size: Option<usize>,
     ) -> Result<Self, ErrorAliasHistory> {
-        let state = SequentialState::new(path.into(), max_size)?;
+        let path = path.into();
+        let state = SequentialState::new(&path, max_size)?;
         let pwd = std::env::current_dir().expect("can't figure out local directory");
-        Ok(AliasHistory { state, pwd })
+        // `history --pwd` compares canonical paths.
+        let pwd = pwd.canonicalize().unwrap_or(pwd);
+        let hostname = hostname::get()
+            .ok()
+            .map(|h| h.to_string_lossy().to_string());
+        let user = std::env::var("USER")
+            .or_else(|_| std::env::var("LOGNAME"))
+            .ok();
+        Ok(AliasHistory {
+            state,
+            path,
+            max_size,
+            pwd,
+            hostname,
+            user,
+            last_put: None,
+        })
+    }
+}
+
+impl AliasHistory {
+    /// returns all the entries, from the oldest to the most recent one.
+    pub fn entries(&self) -> Result<Vec<HistoryEntry>, ErrorAliasHistory> {
+        Ok(self.state.entries()?.collect())
+    }
+
+    pub fn interactor<D>(&self, delegate: D) -> SequentialStateInteractor<HistoryEntry, D> {
+        SequentialStateInteractor::new(&self.path, delegate, self.max_size)
     }
 }
 
 impl SamHistory for AliasHistory {
     fn put(&mut self, alias: ResolvedAlias) -> Result<(), ErrorSamEngine> {
-        let entry = HistoryEntry {
-            r: alias,
-            pwd: self.pwd.to_string_lossy().to_string(),
+        let mut entry = HistoryEntry::new(alias, self.pwd.to_string_lossy());
+        entry.hostname = self.hostname.clone();
+        entry.user = self.user.clone();
+        entry.version = Some(env!("CARGO_PKG_VERSION").to_string());
+        self.state
+            .push(entry.clone())
+            .map_err(|err| ErrorSamEngine::HistoryNotAvailable(Box::new(err)))?;
+        self.last_put = Some(entry);
+        Ok(())
+    }
+
+    fn set_last_outcome(&mut self, outcome: ExecutionOutcome) -> Result<(), ErrorSamEngine> {
+        let last_put = match self.last_put.take() {
+            Some(entry) => entry,
+            None => return Ok(()),
         };
+        // other sam processes might have appended entries in the meantime
         self.state
-            .push(entry)
+            .update_last_matching(
+                |e| e == &last_put,
+                |e| *e = e.clone().with_outcome(&outcome),
+            )
+            .map(|_| ())
             .map_err(|err| ErrorSamEngine::HistoryNotAvailable(Box::new(err)))
     }
 
-    fn get_last_n(&self, n: usize) -> Result<Vec<ResolvedAlias>, ErrorSamEngine> {
+    fn get_last_n_entries(&self, n: usize) -> Result<Vec<HistoryEntry>, ErrorSamEngine> {
         let entries = self
             .state
             .entries()
             .map_err(|err| ErrorSamEngine::HistoryNotAvailable(Box::new(err)))?;
-        let entries_vec: Vec<ResolvedAlias> = entries.map(|e| e.r).collect();
+        let entries_vec: Vec<HistoryEntry> = entries.collect();
         if entries_vec.len() > n {
             let skip = entries_vec.len() - n;
             Ok(entries_vec.into_iter().skip(skip).collect())
@@ -58,19 +109,17 @@ impl SamHistory for AliasHistory {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
-struct HistoryEntry {
-    r: ResolvedAlias,
-    pwd: String,
-}
-
 #[cfg(test)]
 mod tests {
     use sam_core::{
         engines::SamHistory,
-        entities::{aliases::ResolvedAlias, choices::Choice, identifiers::Identifier},
+        entities::{
+            aliases::ResolvedAlias, choices::Choice, history::ExecutionOutcome,
+            identifiers::Identifier,
+        },
     };
     use sam_utils::fsutils;
+    use std::time::Duration;
 
     use super::AliasHistory;
 
@@ -96,5 +145,39 @@ mod tests {
     }
 
     #[test]
-    fn test_history_get_last_n() {}
+    fn test_history_set_last_outcome() {
+        let f = fsutils::TempFile::new().expect("can't create temp file for test");
+        let mut hist = AliasHistory::new(&f.path, None).expect("can't create history file");
+        let test = ResolvedAlias::new(
+            Identifier::with_namespace("alias", Some("ns")),
+            String::from("desc"),
+            String::from("false"),
+            String::from("false"),
+            maplit::hashmap! {},
+        );
+        hist.put(test).expect("The put should succeed");
+        // an entry appended by another process
+        let mut other = AliasHistory::new(&f.path, None).expect("can't open history file");
+        let other_alias = ResolvedAlias::new(
+            Identifier::with_namespace("other", Some("ns")),
+            String::from("desc"),
+            String::from("true"),
+            String::from("true"),
+            maplit::hashmap! {},
+        );
+        other.put(other_alias).expect("The put should succeed");
+        hist.set_last_outcome(ExecutionOutcome {
+            exit_code: Some(1),
+            duration: Duration::from_millis(20),
+            printed: false,
+        })
+        .expect("should be able to record the outcome");
+
+        let entries = hist.get_last_n_entries(2).expect("should be able to read");
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].exit_code, Some(1));
+        assert_eq!(entries[0].duration_ms, Some(20));
+        assert!(entries[0].hostname.is_some());
+        assert_eq!(entries[1].exit_code, None);
+    }
 }
@@ -0,0 +1,176 @@
+use std::cell::OnceCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use sam_core::algorithms::VarsDefaultValues;
+use sam_core::entities::choices::Choice;
+use sam_core::entities::history::HistoryEntry;
+use sam_core::entities::identifiers::Identifier;
+
+use crate::sequential_state::SequentialState;
+
+/// HistoryContextDefaults provides, for every var, the choice that was last made
+/// in the current directory or, failing that, in its nearest ancestor.
+/// Those values are meant to be suggested to the user, not applied without asking.
+#[derive(Debug, Default, Clone)]
+pub struct HistoryContextDefaults {
+    choices: OnceCell<HashMap<Identifier, Choice>>,
+    // the history file and the current directory, the file is read on the first lookup.
+    source: Option<(PathBuf, PathBuf)>,
+}
+
+impl HistoryContextDefaults {
+    /// the history is read once, the first time a suggestion is looked up,
+    /// most invocations of sam never need one.
+    pub fn from_history_file(path: impl Into<PathBuf>, cwd: impl Into<PathBuf>) -> Self {
+        HistoryContextDefaults {
+            choices: OnceCell::new(),
+            source: Some((path.into(), cwd.into())),
+        }
+    }
+
+    /// entries are expected from the oldest to the most recent one.
+    pub fn from_entries(entries: impl IntoIterator<Item = HistoryEntry>, cwd: &Path) -> Self {
+        HistoryContextDefaults {
+            choices: OnceCell::from(Self::choices_from_entries(entries, cwd)),
+            source: None,
+        }
+    }
+
+    fn choices(&self) -> &HashMap<Identifier, Choice> {
+        self.choices.get_or_init(|| match &self.source {
+            // suggestions are a convenience, an unreadable history doesn't provide any.
+            Some((path, cwd)) => SequentialState::<HistoryEntry>::new(path, None)
+                .and_then(|state| state.entries())
+                .map(|entries| Self::choices_from_entries(entries, cwd))
+                .unwrap_or_default(),
+            None => HashMap::new(),
+        })
+    }
+
+    fn choices_from_entries(
+        entries: impl IntoIterator<Item = HistoryEntry>,
+        cwd: &Path,
+    ) -> HashMap<Identifier, Choice> {
+        // for every var, the depth of the directory the kept choice was made in.
+        let mut depths: HashMap<Identifier, usize> = HashMap::new();
+        let mut choices = HashMap::new();
+        for entry in entries {
+            let pwd = Path::new(&entry.pwd);
+            if !cwd.starts_with(pwd) {
+                continue;
+            }
+            let depth = pwd.components().count();
+            for (id, choice) in entry.alias.choices() {
+                // the stored value of a secret var is a placeholder, not a choice.
+                if entry.alias.is_secret(id) {
+                    continue;
+                }
+                let closer_or_newer = depths.get(id).map(|d| depth >= *d).unwrap_or(true);
+                if closer_or_newer {
+                    depths.insert(id.clone(), depth);
+                    choices.insert(id.clone(), choice.clone());
+                }
+            }
+        }
+        choices
+    }
+}
+
+impl VarsDefaultValues for HistoryContextDefaults {
+    fn default_value(&self, id: &Identifier) -> Option<&Choice> {
+        self.choices().get(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use sam_core::algorithms::VarsDefaultValues;
+    use sam_core::entities::aliases::ResolvedAlias;
+    use sam_core::entities::choices::Choice;
+    use sam_core::entities::history::HistoryEntry;
+    use sam_core::entities::identifiers::Identifier;
+
+    use super::HistoryContextDefaults;
+    use crate::sequential_state::SequentialState;
+    use sam_utils::fsutils::TempFile;
+
+    fn entry(pwd: &str, cluster: &str) -> HistoryEntry {
+        let alias = ResolvedAlias::new(
+            Identifier::with_namespace("alias", Some("k8s")),
+            String::from("desc"),
+            String::from("kubectl --context {{cluster}} get pods"),
+            format!("kubectl --context {} get pods", cluster),
+            maplit::hashmap! {
+                Identifier::with_namespace("cluster", Some("k8s")) => Choice::from_value(cluster),
+            },
+        );
+        HistoryEntry::new(alias, pwd)
+    }
+
+    #[test]
+    fn test_history_context_defaults() {
+        let cluster = Identifier::with_namespace("cluster", Some("k8s"));
+        let entries = vec![
+            entry("/home/user/service", "staging"),
+            entry("/home/user", "dev"),
+            entry("/home/user/service", "prod"),
+            entry("/home/user/other", "test"),
+        ];
+
+        let defaults =
+            HistoryContextDefaults::from_entries(entries.clone(), Path::new("/home/user/service"));
+        assert_eq!(
+            defaults.default_value(&cluster),
+            Some(&Choice::from_value("prod"))
+        );
+
+        let defaults = HistoryContextDefaults::from_entries(
+            entries.clone(),
+            Path::new("/home/user/service/src"),
+        );
+        assert_eq!(
+            defaults.default_value(&cluster),
+            Some(&Choice::from_value("prod"))
+        );
+
+        let defaults =
+            HistoryContextDefaults::from_entries(entries.clone(), Path::new("/home/user"));
+        assert_eq!(
+            defaults.default_value(&cluster),
+            Some(&Choice::from_value("dev"))
+        );
+
+        let defaults = HistoryContextDefaults::from_entries(entries, Path::new("/tmp"));
+        assert_eq!(defaults.default_value(&cluster), None);
+
+        let mut secret = entry("/home/user", "dev");
+        secret.alias = secret.alias.with_secrets(vec![cluster.clone()]);
+        let defaults = HistoryContextDefaults::from_entries(vec![secret], Path::new("/home/user"));
+        assert_eq!(defaults.default_value(&cluster), None);
+    }
+
+    #[test]
+    fn test_history_context_defaults_from_file() {
+        let cluster = Identifier::with_namespace("cluster", Some("k8s"));
+        let f = TempFile::new().expect("can't create temp file for test");
+        let defaults = HistoryContextDefaults::from_history_file(&f.path, "/home/user");
+        let state = SequentialState::new(&f.path, None).expect("can't create history file");
+        state.push(entry("/home/user", "dev")).unwrap();
+        // the file is only read on the first lookup.
+        assert_eq!(
+            defaults.default_value(&cluster),
+            Some(&Choice::from_value("dev"))
+        );
+        state.push(entry("/home/user", "prod")).unwrap();
+        assert_eq!(
+            defaults.default_value(&cluster),
+            Some(&Choice::from_value("dev"))
+        );
+
+        let defaults = HistoryContextDefaults::from_history_file("/nonexistent/history", "/");
+        assert_eq!(defaults.default_value(&cluster), None);
+    }
+}
@@ -1,11 +1,25 @@
 mod associative_state;
 mod history_aliases;
+mod history_context;
 pub mod repositories;
 mod sequential_state;
+mod shell_history;
 mod vars_cache;
+pub use associative_state::AssociativeStateInteractor;
+pub use associative_state::EntrySelector;
 pub use history_aliases::AliasHistory;
 pub use history_aliases::ErrorAliasHistory;
+pub use history_context::HistoryContextDefaults;
+pub use sequential_state::EntrySelector as SequentialEntrySelector;
+pub use sequential_state::ErrorSeqStateInteractor;
+pub use sequential_state::SequentialStateInteractor;
+pub use shell_history::ErrorShellHistory;
+pub use shell_history::ShellHistory;
+pub use shell_history::ShellKind;
+pub use vars_cache::CacheEntry;
 pub use vars_cache::CacheError;
+pub use vars_cache::CacheRecord;
+pub use vars_cache::CacheStats;
 pub use vars_cache::NoopVarsCache;
 pub use vars_cache::RustBreakCache;
 pub use vars_cache::VarsCache;
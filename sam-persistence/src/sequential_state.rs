@@ -85,6 +85,27 @@ where
             .map_err(ErrorSequentialState::ReadFailure)
     }
 
+    /// applies `update` to the most recent entry matching `predicate`.
+    /// Returns false when no entry matched.
+    pub fn update_last_matching(
+        &self,
+        predicate: impl Fn(&V) -> bool,
+        update: impl FnOnce(&mut V),
+    ) -> ModResult<bool> {
+        let db = self.open_db()?;
+        let updated = db
+            .write(|db| match db.iter_mut().rev().find(|e| predicate(e)) {
+                Some(entry) => {
+                    update(entry);
+                    true
+                }
+                None => false,
+            })
+            .map_err(ErrorSequentialState::WriteFailures)?;
+        db.save().map_err(ErrorSequentialState::SaveFailures)?;
+        Ok(updated)
+    }
+
     pub fn delete(&self, position: usize) -> ModResult<()> {
         let db = self.open_db()?;
         db.write(|db| {
@@ -108,6 +129,15 @@ pub trait EntrySelector<V> {
     ) -> Result<Option<usize>, Box<dyn std::error::Error>>;
 }
 
+impl<V, D: EntrySelector<V>> EntrySelector<V> for &D {
+    fn select_entry(
+        &self,
+        data: impl Iterator<Item = (usize, V)>,
+    ) -> Result<Option<usize>, Box<dyn std::error::Error>> {
+        (*self).select_entry(data)
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum ErrorSeqStateInteractor {
     #[error("failed to interract with state because \n->{0}")]
@@ -122,7 +152,7 @@ pub struct SequentialStateInteractor<V, D> {
 }
 
 impl<V, D> SequentialStateInteractor<V, D> {
-    fn new(path: impl AsRef<Path>, delegate: D, max_size: Option<usize>) -> Self {
+    pub(crate) fn new(path: impl AsRef<Path>, delegate: D, max_size: Option<usize>) -> Self {
         SequentialStateInteractor {
             state: SequentialState {
                 path: path.as_ref().to_path_buf(),
@@ -150,6 +180,28 @@ where
         }
         Ok(())
     }
+
+    /// lets the delegate select one of the entries matching the predicate,
+    /// the position of the entry in the state is returned along with it.
+    pub fn select_entry_where(
+        &self,
+        predicate: impl Fn(&V) -> bool,
+    ) -> Result<Option<(usize, V)>, ErrorSeqStateInteractor> {
+        let entries: Vec<(usize, V)> = self.entries()?.filter(|(_, e)| predicate(e)).collect();
+        let selected_element = self
+            .delegate
+            .select_entry(entries.clone().into_iter())
+            .map_err(ErrorSeqStateInteractor::ErrEntrySelector)?;
+        Ok(selected_element.and_then(|position| {
+            entries
+                .into_iter()
+                .find(|(entry_position, _)| entry_position == &position)
+        }))
+    }
+
+    pub fn delete_at(&self, position: usize) -> Result<(), ErrorSeqStateInteractor> {
+        Ok(self.state.delete(position)?)
+    }
 }
 impl<V, D> SequentialStateInteractor<V, D>
 where
@@ -164,7 +216,18 @@ where
 mod tests {
     use sam_utils::fsutils::TempFile;
 
-    use super::{ModResult, SequentialState, Value};
+    use super::{EntrySelector, ModResult, SequentialState, SequentialStateInteractor, Value};
+
+    struct LastEntrySelector;
+
+    impl EntrySelector<i32> for LastEntrySelector {
+        fn select_entry(
+            &self,
+            data: impl Iterator<Item = (usize, i32)>,
+        ) -> Result<Option<usize>, Box<dyn std::error::Error>> {
+            Ok(data.last().map(|(position, _)| position))
+        }
+    }
 
     fn make_temp_state<V: Value>() -> SequentialState<V> {
         let f = TempFile::new().expect("failed to created a temporary file");
@@ -210,4 +273,22 @@ mod tests {
 
     #[test]
     fn test_sequential_state_interactor_delete_entry() {}
+
+    #[test]
+    fn test_sequential_state_interactor_select_entry_where() {
+        let f = TempFile::new().expect("failed to created a temporary file");
+        let state = SequentialState::new(&f.path, None).expect("failed to create a new db");
+        insert_values(&state, &[1, 2, 3, 4, 7]).expect("could not into state");
+        let interactor = SequentialStateInteractor::new(&f.path, &LastEntrySelector, None);
+
+        let selected = interactor
+            .select_entry_where(|v| v % 2 == 0)
+            .expect("could not select an entry");
+        assert_eq!(selected, Some((3, 4)));
+
+        interactor.delete_at(3).expect("could not delete the entry");
+        let returned_values: Vec<i32> =
+            state.entries().expect("call to into_iter failed").collect();
+        assert_eq!(returned_values, vec![1, 2, 3, 7]);
+    }
 }
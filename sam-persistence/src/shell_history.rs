@@ -0,0 +1,211 @@
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+
+/// The shells whose history file format is supported.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShellKind {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// ShellHistory appends the commands run by sam to the history file of the user's shell.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShellHistory {
+    kind: ShellKind,
+    path: PathBuf,
+}
+
+impl ShellHistory {
+    pub fn new(kind: ShellKind, path: impl Into<PathBuf>) -> Self {
+        ShellHistory {
+            kind,
+            path: path.into(),
+        }
+    }
+
+    /// the location each shell uses when not configured otherwise.
+    pub fn default_path(kind: ShellKind, home_dir: &Path) -> PathBuf {
+        match kind {
+            ShellKind::Bash => home_dir.join(".bash_history"),
+            ShellKind::Zsh => home_dir.join(".zsh_history"),
+            ShellKind::Fish => home_dir
+                .join(".local")
+                .join("share")
+                .join("fish")
+                .join("fish_history"),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    /// appends the command. zsh guards its history file with a `$HISTFILE.LOCK` file, the
+    /// other shells are kept off with an exclusive lock on the history file itself.
+    /// bash would read every line of a multi-line command as an entry of its own,
+    /// such commands are left out of its history.
+    pub fn append(
+        &self,
+        command: &str,
+        started_at: u64,
+        elapsed: Duration,
+    ) -> Result<(), ErrorShellHistory> {
+        if self.kind == ShellKind::Bash && command.contains('\n') {
+            return Ok(());
+        }
+        let entry = self.format_entry(command, started_at, elapsed);
+        let _zsh_lock = match self.kind {
+            ShellKind::Zsh => Some(ZshLock::acquire(&self.path)?),
+            _ => None,
+        };
+        let mut f = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        f.lock_exclusive()?;
+        let written = f.write_all(entry.as_bytes()).and_then(|_| f.flush());
+        f.unlock()?;
+        Ok(written?)
+    }
+
+    fn format_entry(&self, command: &str, started_at: u64, elapsed: Duration) -> String {
+        match self.kind {
+            ShellKind::Bash => format!("{}\n", command),
+            // zsh extended history, multi-line commands have their newlines escaped.
+            ShellKind::Zsh => format!(
+                ": {}:{};{}\n",
+                started_at,
+                elapsed.as_secs(),
+                command.replace('\n', "\\\n")
+            ),
+            ShellKind::Fish => format!(
+                "- cmd: {}\n  when: {}\n",
+                command.replace('\\', "\\\\").replace('\n', "\\n"),
+                started_at
+            ),
+        }
+    }
+}
+
+// ZshLock follows the convention of zsh: the history file is locked for as long as
+// `$HISTFILE.LOCK` exists, a lock file older than ZSH_STALE_LOCK is left over by a crash.
+struct ZshLock {
+    path: PathBuf,
+}
+
+const ZSH_STALE_LOCK: Duration = Duration::from_secs(10);
+
+impl ZshLock {
+    fn acquire(history_file: &Path) -> std::io::Result<ZshLock> {
+        let mut path = history_file.as_os_str().to_owned();
+        path.push(".LOCK");
+        let path = PathBuf::from(path);
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut f) => {
+                    writeln!(f, "{}", std::process::id())?;
+                    return Ok(ZshLock { path });
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {
+                    let stale = std::fs::metadata(&path)
+                        .and_then(|m| m.modified())
+                        .map(|modified| modified.elapsed().unwrap_or_default() > ZSH_STALE_LOCK)
+                        .unwrap_or(false);
+                    if stale {
+                        let _ = std::fs::remove_file(&path);
+                    } else {
+                        std::thread::sleep(Duration::from_millis(50));
+                    }
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+impl Drop for ZshLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ErrorShellHistory {
+    #[error("could not write to the shell history file\n-> {0}")]
+    ShellHistoryIO(#[from] std::io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ShellHistory, ShellKind};
+    use sam_utils::fsutils::TempFile;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    #[test]
+    fn test_shell_history_formats() {
+        let cases = vec![
+            (ShellKind::Bash, "ls -l\n"),
+            (ShellKind::Zsh, ": 1640000000:3;ls -l\n"),
+            (ShellKind::Fish, "- cmd: ls -l\n  when: 1640000000\n"),
+        ];
+        for (kind, expected) in cases {
+            let history = ShellHistory::new(kind, "/dev/null");
+            let entry = history.format_entry("ls -l", 1640000000, Duration::from_millis(3500));
+            assert_eq!(entry, expected);
+        }
+
+        let zsh = ShellHistory::new(ShellKind::Zsh, "/dev/null");
+        assert_eq!(
+            zsh.format_entry("echo a\necho b", 1, Duration::from_secs(0)),
+            ": 1:0;echo a\\\necho b\n"
+        );
+    }
+
+    #[test]
+    fn test_shell_history_append() {
+        let f = TempFile::new().expect("can't create temp file for test");
+        std::fs::write(&f.path, "existing\n").unwrap();
+        let history = ShellHistory::new(ShellKind::Bash, &f.path);
+        history
+            .append("ls -l", 1, Duration::from_secs(0))
+            .expect("should append to the history");
+        history
+            .append("pwd", 2, Duration::from_secs(0))
+            .expect("should append to the history");
+        history
+            .append("echo a\necho b", 3, Duration::from_secs(0))
+            .expect("multi-line commands should be left out");
+        let content = std::fs::read_to_string(&f.path).unwrap();
+        assert_eq!(content, "existing\nls -l\npwd\n");
+    }
+
+    #[test]
+    fn test_shell_history_zsh_lock() {
+        let f = TempFile::new().expect("can't create temp file for test");
+        let lock = PathBuf::from(format!("{}.LOCK", f.path.display()));
+        std::fs::write(&lock, "1\n").unwrap();
+        let history = ShellHistory::new(ShellKind::Zsh, &f.path);
+        let writer = {
+            let history = history.clone();
+            std::thread::spawn(move || history.append("ls -l", 1, Duration::from_secs(0)))
+        };
+        std::thread::sleep(Duration::from_millis(200));
+        // zsh holds the lock, nothing is written until it is released.
+        assert_eq!(std::fs::read_to_string(&f.path).unwrap(), "");
+        std::fs::remove_file(&lock).unwrap();
+        writer
+            .join()
+            .unwrap()
+            .expect("should append to the history");
+        assert_eq!(std::fs::read_to_string(&f.path).unwrap(), ": 1:0;ls -l\n");
+        assert!(!lock.exists());
+    }
+}
@@ -1,15 +1,24 @@
+use sam_core::entities::identifiers::Identifier;
+use sam_core::entities::namespaces::Namespace;
 use serde::Deserialize;
 use serde::Serialize;
+use std::collections::BTreeSet;
 use std::path::Path;
 use std::time::Duration;
 use std::time::SystemTimeError;
 use thiserror::Error;
 
+use crate::associative_state::AssociativeStateInteractor;
 use crate::associative_state::AssociativeStateWithTTL;
 use crate::associative_state::ErrorAssociativeState;
 
 pub trait VarsCache {
-    fn put(&self, command: &dyn AsRef<str>, output: &dyn AsRef<str>) -> Result<(), CacheError>;
+    fn put(
+        &self,
+        var: &Identifier,
+        command: &dyn AsRef<str>,
+        output: &dyn AsRef<str>,
+    ) -> Result<(), CacheError>;
     fn get(&self, command: &dyn AsRef<str>) -> Result<Option<String>, CacheError>;
 }
 
@@ -22,6 +31,26 @@ pub struct RustBreakCache {
 pub struct CacheEntry {
     pub command: String,
     pub output: String,
+    // the vars that ran the command, several vars can share the same command.
+    // Entries written by older versions of sam don't know which vars produced them.
+    #[serde(default)]
+    pub vars: BTreeSet<Identifier>,
+}
+
+/// A cache entry along with the time at which it was stored.
+#[derive(Debug, Clone)]
+pub struct CacheRecord {
+    pub entry: CacheEntry,
+    pub cached_at: u64,
+    pub expired: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub expired: usize,
+    pub size: u64,
+    pub oldest: Option<u64>,
 }
 
 impl RustBreakCache {
@@ -35,20 +64,99 @@ impl RustBreakCache {
         Ok(self.state.entries()?.map(|(_, v)| v))
     }
 
+    pub fn records(&self) -> Result<impl Iterator<Item = CacheRecord> + '_, CacheError> {
+        Ok(self
+            .state
+            .timestamped_entries()?
+            .map(move |(_, entry, when)| CacheRecord {
+                entry,
+                cached_at: when,
+                expired: self.state.is_expired(when),
+            }))
+    }
+
+    pub fn record(&self, key: &str) -> Result<Option<CacheRecord>, CacheError> {
+        Ok(self.records()?.find(|r| r.entry.command == key))
+    }
+
     pub fn clear_cache(&self) -> Result<(), CacheError> {
         for (key, _) in self.state.entries()? {
             self.state.delete(key)?;
         }
         Ok(())
     }
+
+    /// detaches the provided var from the entries it produced, the entries that no other
+    /// var produced are deleted. Returns the number of entries the var was detached from.
+    pub fn clear_var(&self, var: &Identifier) -> Result<usize, CacheError> {
+        self.clear_where(|v| v == var)
+    }
+
+    /// detaches the vars of the provided namespace from the entries they produced, see
+    /// `clear_var`. Returns the number of entries the vars were detached from.
+    pub fn clear_namespace(&self, namespace: &str) -> Result<usize, CacheError> {
+        self.clear_where(|v| v.namespace() == Some(namespace))
+    }
+
+    pub fn stats(&self) -> Result<CacheStats, CacheError> {
+        let mut stats = CacheStats {
+            size: std::fs::metadata(self.state.path())?.len(),
+            ..CacheStats::default()
+        };
+        for record in self.records()? {
+            stats.entries += 1;
+            if record.expired {
+                stats.expired += 1;
+            }
+            stats.oldest = stats
+                .oldest
+                .map(|o| o.min(record.cached_at))
+                .or(Some(record.cached_at));
+        }
+        Ok(stats)
+    }
+
+    pub fn interactor<D>(&self, delegate: D) -> AssociativeStateInteractor<CacheEntry, D> {
+        self.state.interactor(delegate)
+    }
+
+    fn clear_where(&self, cleared: impl Fn(&Identifier) -> bool) -> Result<usize, CacheError> {
+        let mut detached = 0;
+        for (key, mut entry) in self.state.entries()? {
+            let before = entry.vars.len();
+            entry.vars.retain(|v| !cleared(v));
+            if entry.vars.len() == before {
+                continue;
+            }
+            detached += 1;
+            if entry.vars.is_empty() {
+                self.state.delete(key)?;
+            } else {
+                self.state.update(key, entry)?;
+            }
+        }
+        Ok(detached)
+    }
 }
 
 impl VarsCache for RustBreakCache {
-    fn put(&self, command: &dyn AsRef<str>, output: &dyn AsRef<str>) -> Result<(), CacheError> {
+    fn put(
+        &self,
+        var: &Identifier,
+        command: &dyn AsRef<str>,
+        output: &dyn AsRef<str>,
+    ) -> Result<(), CacheError> {
         let key = command.as_ref().to_string();
+        let mut vars = self
+            .state
+            .get(&key)?
+            .map(|entry| entry.vars)
+            .unwrap_or_default();
+        vars.insert(var.clone());
         let entry = CacheEntry {
             command: key.clone(),
             output: output.as_ref().to_string(),
+            vars,
         };
         Ok(self.state.put(key, entry)?)
     }
@@ -62,7 +170,12 @@ impl VarsCache for RustBreakCache {
 pub struct NoopVarsCache {}
 
 impl VarsCache for NoopVarsCache {
-    fn put(&self, _command: &dyn AsRef<str>, _output: &dyn AsRef<str>) -> Result<(), CacheError> {
+    fn put(
+        &self,
+        _var: &Identifier,
+        _command: &dyn AsRef<str>,
+        _output: &dyn AsRef<str>,
+    ) -> Result<(), CacheError> {
         Ok(())
     }
     fn get(&self, _command: &dyn AsRef<str>) -> Result<Option<String>, CacheError> {
@@ -78,11 +191,14 @@ pub enum CacheError {
     CantGetTimeStamp(#[from] SystemTimeError),
     #[error("could not interract with cache because\n-> {0}")]
     ErrAssociativeState(#[from] ErrorAssociativeState),
+    #[error("could not read the cache file because\n-> {0}")]
+    CacheFileIO(#[from] std::io::Error),
 }
 
 #[cfg(test)]
 mod tests {
     use crate::vars_cache::{RustBreakCache, VarsCache};
+    use sam_core::entities::identifiers::Identifier;
     use sam_utils::fsutils::TempFile;
     use std::time::Duration;
 
@@ -91,8 +207,9 @@ mod tests {
         let tmp_dir = TempFile::new().expect("can't create a temporary file");
         let ttl = Duration::from_secs(90);
         let cache = RustBreakCache::with_ttl(&tmp_dir.path, &ttl).expect("Can't open cache");
+        let var = Identifier::with_namespace("var", Some("ns"));
         cache
-            .put(&String::from("command"), &String::from("output"))
+            .put(&var, &String::from("command"), &String::from("output"))
             .expect("can't write in rustbreak cache");
 
         let cache2 = RustBreakCache::with_ttl(&tmp_dir.path, &ttl).expect("Can't open cache");
@@ -104,7 +221,7 @@ mod tests {
 
         let cache = RustBreakCache::with_ttl(&tmp_dir.path, &ttl).expect("Can't open cache");
         cache
-            .put(&String::from("command2"), &String::from("output"))
+            .put(&var, &String::from("command2"), &String::from("output"))
             .expect("can't write in rustbreak cache");
 
         let value = cache2
@@ -113,4 +230,59 @@ mod tests {
             .expect("can't retrieve the value from rustbreak cache");
         assert_eq!(value, "output");
     }
+
+    #[test]
+    pub fn test_rustbreak_cache_clear_var_and_namespace() {
+        let tmp_dir = TempFile::new().expect("can't create a temporary file");
+        let ttl = Duration::from_secs(90);
+        let cache = RustBreakCache::with_ttl(&tmp_dir.path, &ttl).expect("Can't open cache");
+        let var_1 = Identifier::with_namespace("var_1", Some("ns1"));
+        let var_2 = Identifier::with_namespace("var_2", Some("ns1"));
+        let var_3 = Identifier::with_namespace("var_3", Some("ns2"));
+        for (var, cmd) in [(&var_1, "cmd1"), (&var_2, "cmd2"), (&var_3, "cmd3")] {
+            cache
+                .put(var, &cmd, &"output")
+                .expect("can't write in rustbreak cache");
+        }
+
+        let stats = cache.stats().expect("can't compute cache stats");
+        assert_eq!(stats.entries, 3);
+        assert_eq!(stats.expired, 0);
+        assert!(stats.oldest.is_some());
+
+        assert_eq!(cache.clear_var(&var_2).expect("can't clear var"), 1);
+        assert!(cache.get(&"cmd2").expect("can't read cache").is_none());
+        assert_eq!(cache.clear_namespace("ns2").expect("can't clear ns"), 1);
+        assert!(cache.get(&"cmd3").expect("can't read cache").is_none());
+
+        let record = cache
+            .record("cmd1")
+            .expect("can't read cache")
+            .expect("cmd1 should still be cached");
+        assert_eq!(record.entry.vars, maplit::btreeset! {var_1});
+        assert!(!record.expired);
+    }
+
+    #[test]
+    pub fn test_rustbreak_cache_shared_command() {
+        let tmp_dir = TempFile::new().expect("can't create a temporary file");
+        let ttl = Duration::from_secs(90);
+        let cache = RustBreakCache::with_ttl(&tmp_dir.path, &ttl).expect("Can't open cache");
+        let var_1 = Identifier::with_namespace("branch", Some("git"));
+        let var_2 = Identifier::with_namespace("branch", Some("gh"));
+        for var in [&var_1, &var_2] {
+            cache
+                .put(var, &"git branch", &"main")
+                .expect("can't write in rustbreak cache");
+        }
+        let record = cache.record("git branch").unwrap().unwrap();
+        assert_eq!(record.entry.vars, maplit::btreeset! {var_1.clone(), var_2});
+
+        assert_eq!(cache.clear_namespace("gh").expect("can't clear ns"), 1);
+        let cleared = cache.record("git branch").unwrap().unwrap();
+        assert_eq!(cleared.entry.vars, maplit::btreeset! {var_1.clone()});
+        assert_eq!(cleared.cached_at, record.cached_at);
+        assert_eq!(cache.clear_var(&var_1).expect("can't clear var"), 1);
+        assert!(cache.record("git branch").unwrap().is_none());
+    }
 }
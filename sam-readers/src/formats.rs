@@ -0,0 +1,138 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::path::Path;
+use thiserror::Error;
+
+lazy_static! {
+    static ref UNKNOWN_FIELD_RE: Regex =
+        Regex::new(r"unknown field `(?P<field>[^`]+)`, expected (?:one of )?(?P<expected>`[^`]+`(?:(?:, | or )`[^`]+`)*)").unwrap();
+    static ref QUOTED_FIELD_RE: Regex = Regex::new(r"`(?P<field>[^`]+)`").unwrap();
+}
+
+// beyond this number of edits, a known field is unlikely to be what was meant.
+const MAX_SUGGESTION_DISTANCE: usize = 3;
+
+/// The file formats aliases and vars can be defined in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileFormat {
+    Yaml,
+    Toml,
+    Json,
+}
+
+impl FileFormat {
+    /// detects the format from the extension of the file, if it is a supported one.
+    pub fn from_path(path: &'_ Path) -> Option<FileFormat> {
+        match path.extension()?.to_str()? {
+            "yaml" | "yml" => Some(FileFormat::Yaml),
+            "toml" => Some(FileFormat::Toml),
+            "json" => Some(FileFormat::Json),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn parse<T>(&self, content: &str) -> Result<T, ErrorsSerde>
+    where
+        T: DeserializeOwned,
+    {
+        match self {
+            FileFormat::Yaml => Ok(serde_yaml::from_str(content)?),
+            FileFormat::Json => Ok(serde_json::from_str(content)?),
+            FileFormat::Toml => Ok(toml::from_str(content)?),
+        }
+    }
+
+    /// parses a list of definitions. YAML and JSON files hold the list itself,
+    /// TOML documents can't so the list is expected under `key` (ie `[[aliases]]`).
+    pub(crate) fn parse_list<T>(
+        &self,
+        content: &str,
+        key: &'static str,
+    ) -> Result<Vec<T>, ErrorsSerde>
+    where
+        T: DeserializeOwned,
+    {
+        match self {
+            FileFormat::Yaml | FileFormat::Json => self.parse(content),
+            // deserializing the whole document keeps the position of errors.
+            FileFormat::Toml => {
+                let mut document: BTreeMap<String, Vec<T>> = toml::from_str(content)?;
+                Ok(document.remove(key).unwrap_or_default())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ErrorsSerde {
+    #[error("{0}")]
+    Yaml(#[from] serde_yaml::Error),
+    #[error("{0}")]
+    TomlDe(#[from] toml::de::Error),
+    #[error("{0}")]
+    TomlSer(#[from] toml::ser::Error),
+    #[error("{0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl ErrorsSerde {
+    /// the 1-indexed line and column the error occured at, when known.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        match self {
+            ErrorsSerde::Yaml(e) => e.location().map(|l| (l.line(), l.column())),
+            ErrorsSerde::TomlDe(e) => e.line_col().map(|(line, col)| (line + 1, col + 1)),
+            ErrorsSerde::TomlSer(_) => None,
+            ErrorsSerde::Json(e) if e.line() > 0 => Some((e.line(), e.column())),
+            ErrorsSerde::Json(_) => None,
+        }
+    }
+}
+
+impl ErrorsSerde {
+    /// the closest known field, when the error is about an unknown one.
+    pub fn suggestion(&self) -> Option<String> {
+        let message = self.to_string();
+        let captures = UNKNOWN_FIELD_RE.captures(&message)?;
+        let field = &captures["field"];
+        QUOTED_FIELD_RE
+            .captures_iter(&captures["expected"])
+            .map(|c| c["field"].to_string())
+            .map(|candidate| (strsim::damerau_levenshtein(field, &candidate), candidate))
+            .filter(|(distance, _)| *distance <= MAX_SUGGESTION_DISTANCE)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, candidate)| candidate)
+    }
+}
+
+/// shows the line the error occured at along with a suggestion, when there is one.
+pub(crate) fn error_context(content: &str, error: &ErrorsSerde) -> String {
+    let mut context = String::new();
+    let position = error.position().filter(|(line, _)| *line > 0);
+    if let Some((line, column)) = position {
+        if let Some(text) = content.lines().nth(line - 1) {
+            let gutter = " ".repeat(line.to_string().len());
+            context.push_str(&format!(
+                "\n{} |\n{} | {}\n{} | {}^",
+                gutter,
+                line,
+                text,
+                gutter,
+                " ".repeat(column.saturating_sub(1))
+            ));
+        }
+    }
+    if let Some(suggestion) = error.suggestion() {
+        context.push_str(&format!("\ndid you mean `{}`?", suggestion));
+    }
+    context
+}
+
+/// formats the file the error comes from as `path:line:column`.
+pub(crate) fn located(path: &'_ Path, error: &ErrorsSerde) -> String {
+    match error.position() {
+        Some((line, column)) => format!("{}:{}:{}", path.display(), line, column),
+        None => path.display().to_string(),
+    }
+}
@@ -0,0 +1,119 @@
+mod navi;
+mod shell;
+
+use sam_core::entities::aliases::Alias;
+use sam_core::entities::commands::Command;
+use sam_core::entities::vars::Var;
+use std::fmt::Display;
+
+pub use navi::import_navi;
+pub use shell::import_shell;
+
+/// Imported holds the definitions converted from a foreign format, along with
+/// the constructs that could not be converted.
+#[derive(Debug, Default)]
+pub struct Imported {
+    pub aliases: Vec<Alias>,
+    pub vars: Vec<Var>,
+    pub skipped: Vec<Skipped>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Skipped {
+    /// where the construct was found, ie `line 12`.
+    pub location: String,
+    pub reason: String,
+}
+
+impl Skipped {
+    pub fn new(location: impl Into<String>, reason: impl Into<String>) -> Self {
+        Skipped {
+            location: location.into(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl Display for Skipped {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.location, self.reason)
+    }
+}
+
+impl Imported {
+    // later definitions replace earlier ones, the way a shell would.
+    fn push_alias(&mut self, alias: Alias) {
+        self.aliases.retain(|a| a.name() != alias.name());
+        self.aliases.push(alias);
+    }
+
+    fn push_var(&mut self, var: Var) {
+        self.vars.retain(|v| v.name() != var.name());
+        self.vars.push(var);
+    }
+
+    /// adds the definitions imported from another source into the same namespace.
+    /// Aliases sharing a name are renamed, the first definition of a var is kept.
+    pub fn merge(&mut self, other: Imported) {
+        for alias in other.aliases {
+            let mut name = alias.name().to_string();
+            let mut suffix = 2;
+            while self.aliases.iter().any(|a| a.name() == name) {
+                name = format!("{}_{}", alias.name(), suffix);
+                suffix += 1;
+            }
+            self.aliases
+                .push(Alias::new(name.as_str(), alias.desc(), alias.alias()));
+        }
+        for var in other.vars {
+            match self.vars.iter().find(|v| v.name() == var.name()) {
+                None => self.vars.push(var),
+                Some(kept) if kept.command() != var.command() || kept.desc() != var.desc() => {
+                    self.skipped.push(Skipped::new(
+                        format!("var `{}`", var.name()),
+                        format!(
+                            "defined again as `{}`, the first definition `{}` was kept",
+                            var.command(),
+                            kept.command()
+                        ),
+                    ))
+                }
+                Some(_) => {}
+            }
+        }
+        self.skipped.extend(other.skipped);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Imported;
+    use sam_core::entities::commands::Command;
+    use sam_core::entities::vars::Var;
+
+    #[test]
+    fn test_merge_keeps_the_first_var_definition() {
+        let mut imported = Imported {
+            vars: vec![Var::from_command("branch", "", "git branch")],
+            ..Imported::default()
+        };
+        imported.merge(Imported {
+            vars: vec![
+                Var::from_command("branch", "", "git branch"),
+                Var::from_command("remote", "", "git remote"),
+            ],
+            ..Imported::default()
+        });
+        imported.merge(Imported {
+            vars: vec![Var::from_command("branch", "", "git branch -a")],
+            ..Imported::default()
+        });
+        let vars: Vec<&str> = imported.vars.iter().map(|v| v.command()).collect();
+        assert_eq!(vars, vec!["git branch", "git remote"]);
+        assert_eq!(imported.skipped.len(), 1);
+        assert_eq!(
+            imported.skipped[0].to_string(),
+            "var `branch`: defined again as `git branch -a`, the first definition `git branch` was kept"
+        );
+    }
+}
@@ -0,0 +1,319 @@
+use super::{Imported, Skipped};
+use lazy_static::lazy_static;
+use regex::Regex;
+use sam_core::entities::aliases::Alias;
+use sam_core::entities::vars::Var;
+use std::collections::HashSet;
+
+lazy_static! {
+    static ref PLACEHOLDER_RE: Regex = Regex::new(r"<(?P<name>[\w-]+)>").unwrap();
+    static ref VAR_DEFINITION_RE: Regex =
+        Regex::new(r"^\$\s*(?P<name>[\w-]+)\s*:\s*(?P<command>.*)$").unwrap();
+    static ref SHELL_VAR_RE: Regex = Regex::new(r"\$\{?(?P<name>[\w-]+)\}?").unwrap();
+}
+
+// navi separates the command of a var from the options given to fzf.
+const FZF_OPTIONS_SEPARATOR: &str = " --- ";
+
+#[derive(Debug, Default)]
+struct Snippet {
+    desc: Option<String>,
+    lines: Vec<String>,
+    location: String,
+}
+
+#[derive(Debug, Default)]
+struct Section {
+    namespace: String,
+    snippets: Vec<Snippet>,
+    // (name, command, location)
+    vars: Vec<(String, String, String)>,
+    skipped: Vec<Skipped>,
+}
+
+/// converts a navi cheatsheet, every `%` section becomes a namespace named after its first tag.
+/// Snippets found before the first `%` header go to `default_namespace`.
+pub fn import_navi(
+    content: &str,
+    source: &str,
+    default_namespace: &str,
+) -> Vec<(String, Imported)> {
+    let mut sections = vec![];
+    let mut section = Section {
+        namespace: sanitize(default_namespace),
+        ..Section::default()
+    };
+    let mut snippet = Snippet::default();
+
+    for (idx, line) in content.lines().enumerate() {
+        let location = format!("{}:{}", source, idx + 1);
+        let trimmed = line.trim();
+        if trimmed.starts_with(';') {
+            continue;
+        }
+        if !trimmed.is_empty() && !trimmed.starts_with(['%', '#', '$', '@']) {
+            if snippet.lines.is_empty() {
+                snippet.location = location;
+            }
+            snippet.lines.push(line.trim_end().to_string());
+            continue;
+        }
+
+        // any other line ends the current snippet
+        let desc = snippet.desc.take();
+        if !snippet.lines.is_empty() {
+            snippet.desc = desc;
+            section.snippets.push(std::mem::take(&mut snippet));
+        }
+
+        if let Some(tags) = trimmed.strip_prefix('%') {
+            let namespace = tags
+                .split(',')
+                .map(str::trim)
+                .find(|t| !t.is_empty())
+                .map(sanitize)
+                .unwrap_or_else(|| sanitize(default_namespace));
+            let previous = std::mem::replace(
+                &mut section,
+                Section {
+                    namespace,
+                    ..Section::default()
+                },
+            );
+            sections.push(previous);
+        } else if let Some(desc) = trimmed.strip_prefix('#') {
+            snippet.desc = Some(desc.trim().to_string());
+        } else if trimmed.starts_with('$') {
+            match VAR_DEFINITION_RE.captures(trimmed) {
+                Some(c) if !c["command"].trim().is_empty() => section.vars.push((
+                    c["name"].to_string(),
+                    c["command"].trim().to_string(),
+                    location,
+                )),
+                _ => section
+                    .skipped
+                    .push(Skipped::new(location, "malformed variable definition")),
+            }
+        } else if trimmed.starts_with('@') {
+            section.skipped.push(Skipped::new(
+                location,
+                "`@` extends another cheatsheet, the vars it refers to must be defined by hand",
+            ));
+        }
+    }
+    if !snippet.lines.is_empty() {
+        section.snippets.push(snippet);
+    }
+    sections.push(section);
+
+    sections
+        .into_iter()
+        .filter(|s| !s.snippets.is_empty() || !s.vars.is_empty() || !s.skipped.is_empty())
+        .map(|s| (s.namespace.clone(), s.convert()))
+        .collect()
+}
+
+impl Section {
+    fn convert(self) -> Imported {
+        let mut imported = Imported::default();
+        let defined: HashSet<String> = self.vars.iter().map(|(n, _, _)| sanitize(n)).collect();
+
+        for (name, command, location) in &self.vars {
+            let command = match command.split_once(FZF_OPTIONS_SEPARATOR) {
+                Some((command, options)) => {
+                    imported.skipped.push(Skipped::new(
+                        location.as_str(),
+                        format!(
+                            "the fzf options `{}` of var `{}` were dropped",
+                            options.trim(),
+                            name
+                        ),
+                    ));
+                    command.trim()
+                }
+                None => command.as_str(),
+            };
+            // navi exposes the other vars to the command as environment variables.
+            let command = SHELL_VAR_RE.replace_all(command, |c: &regex::Captures| {
+                let var = sanitize(&c["name"]);
+                if defined.contains(&var) {
+                    format!("{{{{ {} }}}}", var)
+                } else {
+                    c[0].to_string()
+                }
+            });
+            imported.push_var(Var::from_command(
+                sanitize(name),
+                format!("{} (imported from navi)", name),
+                command.to_string(),
+            ));
+        }
+
+        let mut names: HashSet<String> = HashSet::new();
+        for snippet in self.snippets {
+            let body = snippet.lines.join("\n");
+            let command = PLACEHOLDER_RE.replace_all(&body, |c: &regex::Captures| {
+                format!("{{{{ {} }}}}", sanitize(&c["name"]))
+            });
+            for c in PLACEHOLDER_RE.captures_iter(&body) {
+                let var = sanitize(&c["name"]);
+                if !defined.contains(&var) && !imported.vars.iter().any(|v| v.name().name() == var)
+                {
+                    imported.push_var(Var::from_input(
+                        var.as_str(),
+                        &format!("{} (imported from navi)", &c["name"]),
+                        &c["name"],
+                    ));
+                }
+            }
+            let desc = snippet
+                .desc
+                .clone()
+                .unwrap_or_else(|| snippet.lines[0].trim().to_string());
+            let mut name = slug(&desc);
+            let mut suffix = 2;
+            while names.contains(&name) {
+                name = format!("{}_{}", slug(&desc), suffix);
+                suffix += 1;
+            }
+            names.insert(name.clone());
+            imported
+                .aliases
+                .push(Alias::new(name.as_str(), desc.as_str(), command.as_ref()));
+        }
+        imported.skipped.extend(self.skipped);
+        imported
+    }
+}
+
+// navi names may contain dashes, sam identifiers may not.
+fn sanitize(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+const MAX_SLUG_LEN: usize = 40;
+
+// turns a description into an alias name, ie `Change branch` into `change_branch`.
+fn slug(desc: &str) -> String {
+    let mut slug = String::new();
+    for c in desc.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('_') {
+            slug.push('_');
+        }
+        if slug.len() >= MAX_SLUG_LEN {
+            break;
+        }
+    }
+    let slug = slug.trim_end_matches('_');
+    if slug.is_empty() {
+        String::from("snippet")
+    } else {
+        slug.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::import_navi;
+    use sam_core::entities::aliases::Alias;
+    use sam_core::entities::commands::Command;
+
+    #[test]
+    fn test_import_navi() {
+        let content = r#"
+% git, code
+
+# Change branch
+git checkout <branch>
+
+# Delete a branch
+git branch -d <branch>
+
+# Change branch
+git switch <branch>
+
+$ branch: git branch | awk '{print $NF}'
+
+% docker-compose
+
+; not converted to anything
+# Logs of a service
+docker-compose logs -f <service-name> --tail <lines>
+
+$ service-name: docker-compose config --services --- --multi
+@ common
+"#;
+        let imported = import_navi(content, "git.cheat", "git");
+        assert_eq!(imported.len(), 2);
+
+        let (namespace, git) = &imported[0];
+        assert_eq!(namespace, "git");
+        assert_eq!(
+            git.aliases,
+            vec![
+                Alias::new(
+                    "change_branch",
+                    "Change branch",
+                    "git checkout {{ branch }}"
+                ),
+                Alias::new(
+                    "delete_a_branch",
+                    "Delete a branch",
+                    "git branch -d {{ branch }}"
+                ),
+                Alias::new(
+                    "change_branch_2",
+                    "Change branch",
+                    "git switch {{ branch }}"
+                ),
+            ]
+        );
+        assert_eq!(git.vars.len(), 1);
+        assert_eq!(git.vars[0].command(), "git branch | awk '{print $NF}'");
+        assert!(git.skipped.is_empty());
+
+        let (namespace, compose) = &imported[1];
+        assert_eq!(namespace, "docker_compose");
+        assert_eq!(
+            compose.aliases,
+            vec![Alias::new(
+                "logs_of_a_service",
+                "Logs of a service",
+                "docker-compose logs -f {{ service_name }} --tail {{ lines }}"
+            )]
+        );
+        let vars: Vec<(String, bool)> = compose
+            .vars
+            .iter()
+            .map(|v| (v.name().name().to_string(), v.is_input()))
+            .collect();
+        assert_eq!(
+            vars,
+            vec![
+                (String::from("service_name"), false),
+                (String::from("lines"), true)
+            ]
+        );
+        assert_eq!(
+            compose.vars[0].command(),
+            "docker-compose config --services"
+        );
+        let locations: Vec<&str> = compose
+            .skipped
+            .iter()
+            .map(|s| s.location.as_str())
+            .collect();
+        assert_eq!(locations, vec!["git.cheat:21", "git.cheat:22"]);
+    }
+}
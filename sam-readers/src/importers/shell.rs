@@ -0,0 +1,278 @@
+use super::{Imported, Skipped};
+use lazy_static::lazy_static;
+use regex::Regex;
+use sam_core::entities::aliases::Alias;
+use sam_core::entities::vars::Var;
+
+lazy_static! {
+    // matches `name() {`, `function name {` and `function name() {`,
+    // the opening brace may also be on the next line.
+    static ref FUNCTION_RE: Regex = Regex::new(
+        r"^(?:function\s+(?P<kw_name>[\w.:-]+)\s*(?:\(\s*\))?|(?P<name>[\w.:-]+)\s*\(\s*\))\s*(?P<rest>.*)$"
+    )
+    .unwrap();
+    static ref POSITIONAL_RE: Regex = Regex::new(r"\$(?:\{(?P<braced>[1-9])\}|(?P<bare>[1-9]))").unwrap();
+}
+
+// constructs that only make sense inside a real shell function.
+const UNSUPPORTED_IN_FUNCTIONS: &[&str] = &[
+    "$@", "$*", "$#", "${@", "${*", "${#", "$0", "shift", "local ", "return", "getopts",
+];
+
+/// converts the `alias` definitions and the simple functions of a bash or zsh file.
+/// The positional parameters of functions become `from_input` vars.
+pub fn import_shell(content: &str) -> Imported {
+    let mut imported = Imported::default();
+    let lines: Vec<&str> = content.lines().collect();
+    let mut comment: Option<String> = None;
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i].trim();
+        let location = format!("line {}", i + 1);
+        i += 1;
+
+        if let Some(text) = line.strip_prefix('#') {
+            comment = Some(text.trim().to_string());
+            continue;
+        }
+        if let Some(definitions) = line.strip_prefix("alias ") {
+            import_alias_line(&mut imported, definitions, comment.take(), &location);
+            continue;
+        }
+        if let Some(captures) = FUNCTION_RE.captures(line) {
+            let name = captures
+                .name("kw_name")
+                .or_else(|| captures.name("name"))
+                .map(|m| m.as_str().to_string())
+                .unwrap_or_default();
+            let mut rest = captures["rest"].trim().to_string();
+            if rest.is_empty() && lines.get(i).map(|l| l.trim() == "{").unwrap_or(false) {
+                rest = String::from("{");
+                i += 1;
+            }
+            match rest.strip_prefix('{') {
+                Some(first_line) => {
+                    let (body, consumed) = function_body(first_line, &lines[i..]);
+                    i += consumed;
+                    match body {
+                        Some(body) => {
+                            import_function(&mut imported, &name, &body, comment.take(), &location)
+                        }
+                        None => imported.skipped.push(Skipped::new(
+                            location,
+                            format!("function `{}` has no closing brace", name),
+                        )),
+                    }
+                }
+                None => imported.skipped.push(Skipped::new(
+                    location,
+                    format!("function `{}` does not have a `{{ ... }}` body", name),
+                )),
+            }
+        }
+        comment = None;
+    }
+    imported
+}
+
+fn import_alias_line(
+    imported: &mut Imported,
+    definitions: &str,
+    comment: Option<String>,
+    location: &str,
+) {
+    let words = match shellwords::split(definitions) {
+        Ok(words) => words,
+        Err(_) => {
+            imported.skipped.push(Skipped::new(
+                location,
+                "mismatched quotes in alias definition",
+            ));
+            return;
+        }
+    };
+    for word in words {
+        if word.starts_with('#') {
+            break;
+        }
+        // flags such as zsh's `alias -g`
+        if word.starts_with('-') {
+            continue;
+        }
+        let (name, command) = match word.split_once('=') {
+            Some((name, command)) if !name.is_empty() => (name, command),
+            _ => continue,
+        };
+        if name.contains(char::is_whitespace) {
+            imported.skipped.push(Skipped::new(
+                location,
+                format!("alias name `{}` contains whitespaces", name),
+            ));
+            continue;
+        }
+        let desc = comment.clone().unwrap_or_else(|| command.to_string());
+        imported.push_alias(Alias::new(name, desc.as_str(), command));
+    }
+}
+
+// collects the lines of the body up to the closing brace, returns the body
+// and how many of the following lines were consumed.
+fn function_body(first_line: &str, next_lines: &[&str]) -> (Option<String>, usize) {
+    let mut depth = 1;
+    let mut body: Vec<String> = vec![];
+    let candidates = std::iter::once(first_line).chain(next_lines.iter().copied());
+    for (consumed, line) in candidates.enumerate() {
+        let mut end = None;
+        for (idx, c) in line.char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                end = Some(idx);
+                break;
+            }
+        }
+        let content = match end {
+            Some(idx) => &line[..idx],
+            None => line,
+        };
+        let content = content.trim().trim_end_matches(';').trim_end();
+        if !content.is_empty() {
+            body.push(content.to_string());
+        }
+        if end.is_some() {
+            return (Some(body.join("\n")), consumed);
+        }
+    }
+    (None, next_lines.len())
+}
+
+fn import_function(
+    imported: &mut Imported,
+    name: &str,
+    body: &str,
+    comment: Option<String>,
+    location: &str,
+) {
+    let unsupported: Vec<&str> = UNSUPPORTED_IN_FUNCTIONS
+        .iter()
+        .copied()
+        .filter(|construct| body.contains(construct))
+        .collect();
+    if !unsupported.is_empty() {
+        imported.skipped.push(Skipped::new(
+            location,
+            format!(
+                "function `{}` uses {} which can't be expressed as an alias",
+                name,
+                unsupported.join(", ")
+            ),
+        ));
+        return;
+    }
+    if body.is_empty() {
+        imported.skipped.push(Skipped::new(
+            location,
+            format!("function `{}` is empty", name),
+        ));
+        return;
+    }
+
+    let var_prefix: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let var_name = |position: &str| format!("{}_arg{}", var_prefix, position);
+    let command = POSITIONAL_RE.replace_all(body, |captures: &regex::Captures| {
+        let position = captures
+            .name("braced")
+            .or_else(|| captures.name("bare"))
+            .map(|m| m.as_str())
+            .unwrap_or_default();
+        format!("{{{{ {} }}}}", var_name(position))
+    });
+
+    let mut positions: Vec<&str> = POSITIONAL_RE
+        .captures_iter(body)
+        .filter_map(|c| c.name("braced").or_else(|| c.name("bare")))
+        .map(|m| m.as_str())
+        .collect();
+    positions.sort_unstable();
+    positions.dedup();
+    for position in positions {
+        imported.push_var(Var::from_input(
+            var_name(position),
+            format!("argument {} of {}", position, name),
+            format!("{} argument {}", name, position),
+        ));
+    }
+
+    let desc = comment.unwrap_or_else(|| format!("shell function {}", name));
+    imported.push_alias(Alias::new(name, desc.as_str(), command.as_ref()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::import_shell;
+    use sam_core::entities::aliases::Alias;
+    use sam_core::entities::vars::Var;
+
+    #[test]
+    fn test_import_shell() {
+        let content = r#"
+# list files
+alias ll='ls -la'
+alias gs="git status" gd='git diff'
+export EDITOR=vim
+
+# creates a directory then moves into it
+mkcd() {
+    mkdir -p "$1"
+    cd "$1"
+}
+
+function greet { echo "hello ${1}, from $2"; }
+
+forward() {
+    some_command "$@"
+}
+
+broken() {
+    echo "never closed"
+"#;
+        let imported = import_shell(content);
+
+        assert_eq!(
+            imported.aliases,
+            vec![
+                Alias::new("ll", "list files", "ls -la"),
+                Alias::new("gs", "git status", "git status"),
+                Alias::new("gd", "git diff", "git diff"),
+                Alias::new(
+                    "mkcd",
+                    "creates a directory then moves into it",
+                    "mkdir -p \"{{ mkcd_arg1 }}\"\ncd \"{{ mkcd_arg1 }}\""
+                ),
+                Alias::new(
+                    "greet",
+                    "shell function greet",
+                    "echo \"hello {{ greet_arg1 }}, from {{ greet_arg2 }}\""
+                ),
+            ]
+        );
+        assert_eq!(
+            imported.vars,
+            vec![
+                Var::from_input("mkcd_arg1", "argument 1 of mkcd", "mkcd argument 1"),
+                Var::from_input("greet_arg1", "argument 1 of greet", "greet argument 1"),
+                Var::from_input("greet_arg2", "argument 2 of greet", "greet argument 2"),
+            ]
+        );
+        assert_eq!(imported.skipped.len(), 2);
+        assert_eq!(imported.skipped[0].location, "line 15");
+        assert!(imported.skipped[0].reason.contains("$@"));
+        assert_eq!(imported.skipped[1].location, "line 19");
+    }
+}
@@ -1,6 +1,21 @@
+mod formats;
+mod importers;
 mod readers;
+mod writers;
+pub use formats::ErrorsSerde;
+pub use formats::FileFormat;
+pub use importers::import_navi;
+pub use importers::import_shell;
+pub use importers::Imported;
+pub use importers::Skipped;
+pub use readers::is_namespace_file;
 pub use readers::read_aliases_from_path;
 pub use readers::read_choices;
 pub use readers::read_vars_repository;
 pub use readers::ErrorsAliasRead;
 pub use readers::ErrorsVarRead;
+pub use writers::aliases_to_yaml;
+pub use writers::append_alias_to_path;
+pub use writers::append_to_path;
+pub use writers::vars_to_yaml;
+pub use writers::ErrorsAliasWrite;
@@ -1,28 +1,73 @@
+use crate::formats::{error_context, located, ErrorsSerde, FileFormat};
 use sam_core::entities::aliases::Alias;
 use sam_core::entities::choices::Choice;
-use sam_core::entities::namespaces::NamespaceUpdater;
+use sam_core::entities::namespaces::{NamespaceUpdater, NAMESPACE_FILE_SUFFIX};
 use sam_core::entities::vars::Var;
 use sam_persistence::repositories::{ErrorsVarsRepository, VarsRepository};
-use std::fs::File;
-use std::io::{BufRead, BufReader, Read};
+use serde::Deserialize;
+use std::io::BufRead;
 use std::path::Path;
 use std::path::PathBuf;
 use thiserror::Error;
 
+// NamespaceDefinition is the content of a single-file namespace, ie `docker.sam.yaml`,
+// the namespace defaults to the stem of the file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NamespaceDefinition {
+    namespace: Option<String>,
+    #[serde(default)]
+    aliases: Vec<Alias>,
+    #[serde(default)]
+    vars: Vec<Var>,
+}
+
+/// tells whether the file defines a whole namespace rather than only its aliases or vars.
+pub fn is_namespace_file(path: &'_ Path) -> bool {
+    let sam_stem = path
+        .file_stem()
+        .and_then(|e| e.to_str())
+        .map(|e| e.ends_with(NAMESPACE_FILE_SUFFIX))
+        .unwrap_or(false);
+    sam_stem && FileFormat::from_path(path).is_some()
+}
+
+// files without a known extension are read as yaml, the historical format.
+fn format_of(path: &'_ Path) -> FileFormat {
+    FileFormat::from_path(path).unwrap_or(FileFormat::Yaml)
+}
+
+fn update_namespaces<T: NamespaceUpdater>(items: &mut [T], namespace: Option<&str>, path: &Path) {
+    for item in items {
+        match namespace {
+            Some(ns) => item.update(ns),
+            None => {
+                item.update_from_path(path);
+            }
+        }
+    }
+}
+
 pub fn read_aliases_from_path(path: &'_ Path) -> Result<Vec<Alias>, ErrorsAliasRead> {
-    let f = File::open(path)?;
-    let l = File::metadata(&f)?.len();
-    if l == 0 {
+    let content = std::fs::read_to_string(path)?;
+    if content.is_empty() {
         return Ok(vec![]);
     }
-    let buf = BufReader::new(f);
-    let mut aliases = read_aliases(buf).map_err(|error| ErrorsAliasRead::AliasSerde {
+    let serde_error = |error| ErrorsAliasRead::AliasSerde {
+        context: error_context(&content, &error),
         error,
         source_file: path.to_path_buf(),
-    })?;
+    };
+    let (mut aliases, namespace) = if is_namespace_file(path) {
+        let definition = read_namespace(&content, format_of(path)).map_err(serde_error)?;
+        (definition.aliases, definition.namespace)
+    } else {
+        let aliases = read_aliases(&content, format_of(path)).map_err(serde_error)?;
+        (aliases, None)
+    };
 
-    for a in aliases.as_mut_slice() {
-        NamespaceUpdater::update_from_path(a, path);
+    update_namespaces(&mut aliases, namespace.as_deref(), path);
+    for a in aliases.as_slice() {
         if a.identifier().inner.contains(' ') {
             return Err(ErrorsAliasRead::AliasInvalidName(
                 a.identifier().to_string(),
@@ -33,11 +78,12 @@ pub fn read_aliases_from_path(path: &'_ Path) -> Result<Vec<Alias>, ErrorsAliasR
     Ok(aliases)
 }
 
-fn read_aliases<T>(r: T) -> Result<Vec<Alias>, serde_yaml::Error>
-where
-    T: Read,
-{
-    serde_yaml::from_reader(r)
+fn read_aliases(content: &str, format: FileFormat) -> Result<Vec<Alias>, ErrorsSerde> {
+    format.parse_list(content, "aliases")
+}
+
+fn read_namespace(content: &str, format: FileFormat) -> Result<NamespaceDefinition, ErrorsSerde> {
+    format.parse(content)
 }
 
 pub fn read_choices<T>(r: T) -> Result<Vec<Choice>, ErrorsChoiceRead>
@@ -61,39 +107,43 @@ where
 }
 
 pub fn read_vars_repository(path: &'_ Path) -> Result<VarsRepository, ErrorsVarRead> {
-    let f = File::open(path)?;
-    let l = File::metadata(&f)?.len();
-    if l == 0 {
+    let content = std::fs::read_to_string(path)?;
+    if content.is_empty() {
         return Ok(VarsRepository::default());
     }
-    let buf = BufReader::new(f);
-    let mut vars = read_vars(buf).map_err(|e| ErrorsVarRead::VarsSerde {
+    let serde_error = |e| ErrorsVarRead::VarsSerde {
+        context: error_context(&content, &e),
         error: e,
         source_file: path.to_path_buf(),
-    })?;
+    };
+    let (mut vars, namespace) = if is_namespace_file(path) {
+        let definition = read_namespace(&content, format_of(path)).map_err(serde_error)?;
+        (definition.vars, definition.namespace)
+    } else {
+        (
+            read_vars(&content, format_of(path)).map_err(serde_error)?,
+            None,
+        )
+    };
 
-    for a in vars.as_mut_slice() {
-        NamespaceUpdater::update_from_path(a, path);
-    }
+    update_namespaces(&mut vars, namespace.as_deref(), path);
 
     Ok(VarsRepository::new(vars.into_iter()))
 }
 
-fn read_vars<T>(r: T) -> Result<Vec<Var>, serde_yaml::Error>
-where
-    T: Read,
-{
-    serde_yaml::from_reader(r)
+fn read_vars(content: &str, format: FileFormat) -> Result<Vec<Var>, ErrorsSerde> {
+    format.parse_list(content, "vars")
 }
 
 #[derive(Debug, Error)]
 pub enum ErrorsAliasRead {
     #[error("invalid caracter in alias `{0}` name allowed caracters are [a-zA-z_1-0-]")]
     AliasInvalidName(String),
-    #[error("parsing error for aliases file {source_file}\n-> {error}.")]
+    #[error("parsing error for aliases file {}\n-> {error}.{context}", located(.source_file, .error))]
     AliasSerde {
-        error: serde_yaml::Error,
+        error: ErrorsSerde,
         source_file: PathBuf,
+        context: String,
     },
     #[error("got an IO error while reading file\n-> {0}")]
     AliasIO(#[from] std::io::Error),
@@ -101,10 +151,11 @@ pub enum ErrorsAliasRead {
 
 #[derive(Debug, Error)]
 pub enum ErrorsVarRead {
-    #[error("parsing error for vars file {source_file}\n-> {error}.")]
+    #[error("parsing error for vars file {}\n-> {error}.{context}", located(.source_file, .error))]
     VarsSerde {
-        error: serde_yaml::Error,
+        error: ErrorsSerde,
         source_file: PathBuf,
+        context: String,
     },
     #[error("got an IO error while reading file\n-> {0}")]
     VarIO(#[from] std::io::Error),
@@ -120,11 +171,18 @@ pub enum ErrorsChoiceRead {
 
 #[cfg(test)]
 mod tests {
-    use super::{read_aliases, read_vars};
+    use super::{
+        is_namespace_file, read_aliases, read_aliases_from_path, read_vars, read_vars_repository,
+        ErrorsAliasRead,
+    };
+    use crate::formats::FileFormat;
+    use sam_core::algorithms::VarsCollection;
     use sam_core::entities::aliases::Alias;
     use sam_core::entities::choices::Choice;
+    use sam_core::entities::identifiers::Identifier;
     use sam_core::entities::vars::Var;
-    use std::io::BufReader;
+    use sam_utils::fsutils::TempDirectory;
+    use std::path::Path;
 
     #[test]
     fn test_read_vars() {
@@ -146,11 +204,9 @@ mod tests {
               from_command: 'echo 1'
             - desc: 'desc4'
               name: 'name4'
-              from_input: prompt"
-            .as_bytes();
+              from_input: prompt";
 
-        let r = BufReader::new(vars_str);
-        let vars_r = read_vars(r);
+        let vars_r = read_vars(vars_str, FileFormat::Yaml);
         assert!(vars_r.is_ok());
         let vars = vars_r.unwrap();
         assert_eq!(vars.len(), 4);
@@ -177,10 +233,8 @@ mod tests {
               alias: 'alias1'
             - desc: 'desc2'
               name: 'name2'
-              alias: 'alias2'"
-            .as_bytes();
-        let r = BufReader::new(aliase_str);
-        let aliases_r = read_aliases(r);
+              alias: 'alias2'";
+        let aliases_r = read_aliases(aliase_str, FileFormat::Yaml);
         assert!(aliases_r.is_ok());
         let aliases = aliases_r.unwrap();
         assert_eq!(aliases.len(), 2);
@@ -191,10 +245,159 @@ mod tests {
             - desc: 'desc1'
               alias: 'alias1'
             - desc: 'desc2'
-              alias: 'alias2'"
-            .as_bytes();
-        let r = BufReader::new(aliase_str);
-        let aliases_r = read_aliases(r);
+              alias: 'alias2'";
+        let aliases_r = read_aliases(aliase_str, FileFormat::Yaml);
         assert!(aliases_r.is_err());
     }
+
+    #[test]
+    fn test_read_toml_and_json() {
+        let toml_str = r#"
+            [[vars]]
+            name = "name1"
+            desc = "desc1"
+            choices = [{ value = "val1", desc = "val1 description" }]
+
+            [[vars]]
+            name = "name3"
+            desc = "desc3"
+            from_command = "echo 1"
+        "#;
+        let json_str = r#"[
+            {"name": "name1", "desc": "desc1", "choices": [{"value": "val1", "desc": "val1 description"}]},
+            {"name": "name3", "desc": "desc3", "from_command": "echo 1"}
+        ]"#;
+        let expected = vec![
+            Var::new(
+                "name1",
+                "desc1",
+                vec![Choice::new("val1", Some("val1 description"))],
+            ),
+            Var::from_command("name3", "desc3", "echo 1"),
+        ];
+        assert_eq!(read_vars(toml_str, FileFormat::Toml).unwrap(), expected);
+        assert_eq!(read_vars(json_str, FileFormat::Json).unwrap(), expected);
+
+        let toml_str = "[[aliases]]\nname = 'name1'\ndesc = 'desc1'\nalias = 'alias1'\n";
+        let json_str = r#"[{"name": "name1", "desc": "desc1", "alias": "alias1"}]"#;
+        let expected = vec![Alias::new("name1", "desc1", "alias1")];
+        assert_eq!(read_aliases(toml_str, FileFormat::Toml).unwrap(), expected);
+        assert_eq!(read_aliases(json_str, FileFormat::Json).unwrap(), expected);
+        assert_eq!(read_aliases("", FileFormat::Toml).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_read_errors_position() {
+        let cases = vec![
+            (
+                "aliases.json",
+                "[\n  {\"name\": \"a\", \"desc\": \"d\"}\n]",
+                "aliases.json:2:",
+            ),
+            (
+                "aliases.toml",
+                "[[aliases]]\nname = 'a'\ndesc = \n",
+                "aliases.toml:3:",
+            ),
+            (
+                "aliases.yaml",
+                "- name: a\n  desc: d\n  alias: [x\n",
+                "aliases.yaml:",
+            ),
+        ];
+        let tmp = TempDirectory::new().expect("can't create temp dir for test");
+        let dir = &tmp.path;
+        for (file_name, content, expected) in cases {
+            let path = dir.join(file_name);
+            std::fs::write(&path, content).unwrap();
+            let err = read_aliases_from_path(Path::new(&path)).unwrap_err();
+            assert!(matches!(err, ErrorsAliasRead::AliasSerde { .. }));
+            let message = err.to_string();
+            assert!(
+                message.contains(&format!("{}", dir.join(expected).display())),
+                "{}",
+                message
+            );
+        }
+    }
+
+    #[test]
+    fn test_read_namespace_file() {
+        let tmp = TempDirectory::new().expect("can't create temp dir for test");
+        let dir = &tmp.path;
+        let content = "
+aliases:
+  - name: pods
+    desc: list pods
+    alias: kubectl --context {{ cluster }} get pods
+vars:
+  - name: cluster
+    desc: the cluster
+    choices:
+      - value: prod
+";
+        let path = dir.join("k8s.sam.yaml");
+        std::fs::write(&path, content).unwrap();
+        assert!(is_namespace_file(&path));
+        assert!(!is_namespace_file(&dir.join("aliases.yaml")));
+
+        let aliases = read_aliases_from_path(&path).expect("should read the aliases");
+        assert_eq!(aliases.len(), 1);
+        assert_eq!(aliases[0].identifier().to_string(), "k8s::pods");
+        let vars = read_vars_repository(&path).expect("should read the vars");
+        let cluster = Identifier::with_namespace("cluster", Some("k8s"));
+        assert!(vars.get(&cluster).is_some());
+
+        std::fs::write(&path, format!("namespace: kubernetes\n{}", content)).unwrap();
+        let aliases = read_aliases_from_path(&path).expect("should read the aliases");
+        assert_eq!(aliases[0].identifier().to_string(), "kubernetes::pods");
+    }
+
+    #[test]
+    fn test_read_errors_context() {
+        let tmp = TempDirectory::new().expect("can't create temp dir for test");
+        let cases = vec![
+            (
+                "vars.yaml",
+                "- name: a\n  desc: d\n  from_comand: ls\n",
+                "3 |   from_comand: ls\n  |   ^\ndid you mean `from_command`?",
+            ),
+            (
+                "vars.json",
+                "[{\"name\": \"a\", \"desc\": \"d\", \"from_inptu\": \"x\"}]",
+                "did you mean `from_input`?",
+            ),
+            (
+                "vars.toml",
+                "[[vars]]\nname = 'a'\ndesc = 'd'\nchoises = []\n",
+                "did you mean `choices`?",
+            ),
+            (
+                "ns.sam.yaml",
+                "alias:\n  - name: a\n",
+                "did you mean `aliases`?",
+            ),
+            (
+                "vars.yml",
+                "- name: a\n  desc: d\n  choices:\n    - value: x\n  from_command: ls\n",
+                "var `a` can only get its choices from one of `choices`, `from_command` or `from_input`, got `choices` and `from_command`",
+            ),
+        ];
+        for (file_name, content, expected) in cases {
+            let path = tmp.path.join(file_name);
+            std::fs::write(&path, content).unwrap();
+            let message = read_vars_repository(&path).unwrap_err().to_string();
+            assert!(message.contains(expected), "{}", message);
+        }
+
+        let aliases = "- name: a\n  desc: d\n  alias: ls\n  defautls: {}\n";
+        let err = read_aliases(aliases, FileFormat::Yaml).unwrap_err();
+        assert_eq!(err.suggestion(), Some(String::from("defaults")));
+        let err = read_aliases(
+            "- name: a\n  desc: d\n  alias: ls\n  x: 1\n",
+            FileFormat::Yaml,
+        )
+        .unwrap_err();
+        assert_eq!(err.suggestion(), None);
+    }
 }
@@ -0,0 +1,224 @@
+use crate::formats::{ErrorsSerde, FileFormat};
+use sam_core::entities::aliases::Alias;
+use sam_core::entities::commands::Command;
+use sam_core::entities::vars::Var;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+// AliasDefinition mirrors the way aliases are written by hand,
+// the namespace is implied by the location of the file.
+#[derive(Serialize)]
+struct AliasDefinition<'a> {
+    name: &'a str,
+    desc: &'a str,
+    alias: &'a str,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    defaults: &'a BTreeMap<String, String>,
+}
+
+impl<'a> From<&'a Alias> for AliasDefinition<'a> {
+    fn from(alias: &'a Alias) -> Self {
+        AliasDefinition {
+            name: alias.name(),
+            desc: alias.desc(),
+            alias: alias.alias(),
+            defaults: alias.raw_defaults(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct VarDefinition<'a> {
+    name: String,
+    desc: &'a str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    choices: Vec<ChoiceDefinition>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    from_command: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    from_input: Option<&'a str>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    secret: bool,
+}
+
+#[derive(Serialize)]
+struct ChoiceDefinition {
+    value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    desc: Option<String>,
+}
+
+impl<'a> From<&'a Var> for VarDefinition<'a> {
+    fn from(var: &'a Var) -> Self {
+        VarDefinition {
+            name: var.name().name().to_string(),
+            desc: var.desc(),
+            choices: var
+                .choices()
+                .iter()
+                .map(|c| ChoiceDefinition {
+                    value: c.value().to_string(),
+                    desc: c.desc().map(ToString::to_string),
+                })
+                .collect(),
+            from_command: Some(var.command()).filter(|c| !c.is_empty()),
+            from_input: var.prompt(),
+            secret: var.is_secret(),
+        }
+    }
+}
+
+/// serializes the aliases as they would be written in an `aliases.yaml` file.
+pub fn aliases_to_yaml(aliases: &[Alias]) -> Result<String, ErrorsSerde> {
+    let definitions: Vec<AliasDefinition> = aliases.iter().map(AliasDefinition::from).collect();
+    to_yaml_list(&definitions)
+}
+
+/// serializes the vars as they would be written in a `vars.yaml` file.
+pub fn vars_to_yaml(vars: &[Var]) -> Result<String, ErrorsSerde> {
+    let definitions: Vec<VarDefinition> = vars.iter().map(VarDefinition::from).collect();
+    to_yaml_list(&definitions)
+}
+
+fn to_yaml_list<T: Serialize>(definitions: &[T]) -> Result<String, ErrorsSerde> {
+    if definitions.is_empty() {
+        return Ok(String::new());
+    }
+    let serialized = serde_yaml::to_string(definitions)?;
+    Ok(serialized.trim_start_matches("---\n").to_string())
+}
+
+/// appends content at the end of the file, on a line of its own, creating the file if needed.
+pub fn append_to_path(path: &'_ Path, content: &str) -> Result<(), std::io::Error> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let existing = std::fs::read_to_string(path).unwrap_or_default();
+    let mut f = OpenOptions::new().create(true).append(true).open(path)?;
+    if !existing.is_empty() && !existing.ends_with('\n') {
+        writeln!(f)?;
+    }
+    f.write_all(content.as_bytes())?;
+    if !content.is_empty() && !content.ends_with('\n') {
+        writeln!(f)?;
+    }
+    Ok(())
+}
+
+/// appends the alias at the end of the aliases file, creating it if needed.
+/// The existing content of yaml and toml files is left untouched, comments included.
+pub fn append_alias_to_path(path: &'_ Path, alias: &Alias) -> Result<(), ErrorsAliasWrite> {
+    let definition = AliasDefinition::from(alias);
+    let serde_error = |error: ErrorsSerde| ErrorsAliasWrite::AliasSerde {
+        error,
+        target_file: path.to_path_buf(),
+    };
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let existing = std::fs::read_to_string(path).unwrap_or_default();
+    let serialized = match FileFormat::from_path(path).unwrap_or(FileFormat::Yaml) {
+        FileFormat::Yaml => to_yaml_list(&[definition]).map_err(serde_error)?,
+        FileFormat::Toml => {
+            let document = BTreeMap::from([("aliases", [definition])]);
+            let serialized = toml::to_string(&document).map_err(|e| serde_error(e.into()))?;
+            if existing.is_empty() {
+                serialized
+            } else {
+                format!("\n{}", serialized)
+            }
+        }
+        // json has no comments to preserve, the whole list is written back.
+        FileFormat::Json => {
+            let mut definitions: Vec<serde_json::Value> = if existing.trim().is_empty() {
+                vec![]
+            } else {
+                serde_json::from_str(&existing).map_err(|e| serde_error(e.into()))?
+            };
+            definitions.push(serde_json::to_value(definition).map_err(|e| serde_error(e.into()))?);
+            let serialized =
+                serde_json::to_string_pretty(&definitions).map_err(|e| serde_error(e.into()))?;
+            std::fs::write(path, format!("{}\n", serialized))?;
+            return Ok(());
+        }
+    };
+
+    Ok(append_to_path(path, &serialized)?)
+}
+
+#[derive(Debug, Error)]
+pub enum ErrorsAliasWrite {
+    #[error("could not serialize the alias for file {target_file}\n-> {error}.")]
+    AliasSerde {
+        error: ErrorsSerde,
+        target_file: PathBuf,
+    },
+    #[error("got an IO error while writing file\n-> {0}")]
+    AliasIO(#[from] std::io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::append_alias_to_path;
+    use crate::read_aliases_from_path;
+    use sam_core::entities::aliases::Alias;
+    use sam_utils::fsutils::{TempDirectory, TempFile};
+
+    #[test]
+    fn test_append_alias_to_path() {
+        let f = TempFile::new().expect("can't create temp file for test");
+        let original = "# my aliases\n- name: list\n  desc: lists files # inline\n  alias: ls -l";
+        std::fs::write(&f.path, original).unwrap();
+
+        let alias = Alias::new(
+            "pods",
+            "get pods",
+            "kubectl --context {{ cluster }} get pods",
+        )
+        .with_defaults(maplit::btreemap! {
+            String::from("cluster") => String::from("prod"),
+        });
+        append_alias_to_path(&f.path, &alias).expect("should append the alias");
+
+        let content = std::fs::read_to_string(&f.path).unwrap();
+        assert!(content.starts_with(original));
+        let aliases = read_aliases_from_path(&f.path).expect("should read the aliases back");
+        assert_eq!(aliases.len(), 2);
+        assert_eq!(aliases[1].name(), "pods");
+        assert_eq!(aliases[1].raw_defaults(), alias.raw_defaults());
+    }
+
+    #[test]
+    fn test_append_alias_to_toml_and_json() {
+        let alias =
+            Alias::new("pods", "get pods", "kubectl get pods").with_defaults(maplit::btreemap! {
+                String::from("cluster") => String::from("prod"),
+            });
+        let dir = TempDirectory::new().expect("can't create temp dir for test");
+        let cases = vec![
+            (
+                "aliases.toml",
+                "# mine\n[[aliases]]\nname = 'list'\ndesc = 'd'\nalias = 'ls'\n",
+            ),
+            (
+                "aliases.json",
+                r#"[{"name": "list", "desc": "d", "alias": "ls"}]"#,
+            ),
+        ];
+        for (file_name, original) in cases {
+            let path = dir.path.join(file_name);
+            std::fs::write(&path, original).unwrap();
+            append_alias_to_path(&path, &alias).expect("should append the alias");
+            let aliases = read_aliases_from_path(&path).expect("should read the aliases back");
+            assert_eq!(aliases.len(), 2);
+            assert_eq!(aliases[0].name(), "list");
+            assert_eq!(aliases[1].name(), "pods");
+            assert_eq!(aliases[1].raw_defaults(), alias.raw_defaults());
+        }
+    }
+}
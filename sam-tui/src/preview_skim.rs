@@ -6,13 +6,16 @@ use sam_core::entities::{choices::Choice, identifiers::Identifier};
 
 pub struct PreviewSkim<'a> {
     pub choices: &'a HashMap<Identifier, Choice>,
-    pub preview_prefix: PathBuf,
+    pub preview_prefix: String,
     pub directory: PathBuf,
 }
 
 impl<'a> PreviewSkim<'a> {
     pub fn new(choices: &'a HashMap<Identifier, Choice>) -> Self {
-        let preview_prefix = std::env::current_exe().expect("toto");
+        let preview_prefix = std::env::current_exe()
+            .expect("toto")
+            .to_string_lossy()
+            .to_string();
         let directory = std::env::current_dir().expect("toto");
         PreviewSkim {
             choices,
@@ -20,6 +23,15 @@ impl<'a> PreviewSkim<'a> {
             directory,
         }
     }
+    /// the global options the preview command needs to load the same configuration.
+    pub fn with_args(mut self, args: &[String]) -> Self {
+        for arg in args {
+            write!(self.preview_prefix, " '{}'", arg.replace('\'', "'\\''"))
+                .expect("Should not fail, please open a bug!:");
+        }
+        self
+    }
+
     pub fn preview_for_identifier(&self, identifier: &Identifier) -> String {
         let mut preview_string = String::with_capacity(50);
         write!(
@@ -31,8 +43,7 @@ impl<'a> PreviewSkim<'a> {
         write!(
             preview_string,
             "{} preview '{}' ",
-            self.preview_prefix.to_string_lossy(),
-            identifier
+            self.preview_prefix, identifier
         )
         .expect("Should not fail, please open a bug!:");
         for (id, choice) in self.choices {
@@ -51,13 +62,13 @@ impl<'a> PreviewSkim<'a> {
             self.directory.to_string_lossy()
         )
         .expect("Should not fail, please open a bug!:");
-        write!(
-            preview_string,
-            "{} preview '{{}}'",
-            self.preview_prefix.to_string_lossy()
-        )
-        .expect("Should not fail, please open a bug!:");
+        write!(preview_string, "{} preview '{{}}'", self.preview_prefix)
+            .expect("Should not fail, please open a bug!:");
 
         preview_string
     }
+
+    pub fn preview_cache_entry(&self) -> String {
+        format!("{} cache show {{}}", self.preview_prefix)
+    }
 }
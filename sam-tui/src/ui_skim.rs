@@ -1,20 +1,22 @@
 use crate::preview_skim::PreviewSkim;
+use sam_core::algorithms::VarsDefaultValues;
 use sam_core::entities::aliases::Alias;
 use sam_core::entities::choices::Choice;
 use sam_core::entities::dependencies::{ErrorsResolver, Resolver};
+use sam_core::entities::history::HistoryEntry;
 use sam_core::entities::identifiers::Identifier;
 use sam_core::entities::processes::ShellCommand;
 use sam_readers::read_choices;
 use sam_utils::fsutils::ErrorsFS;
 use skim::prelude::*;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::Deref;
 use std::process::Command;
 
 use thiserror::Error;
 
-use sam_persistence::VarsCache;
+use sam_persistence::{CacheEntry, EntrySelector, SequentialEntrySelector, VarsCache};
 
 type UISelector = Arc<dyn SkimItem>;
 
@@ -23,6 +25,10 @@ pub struct UserInterface {
     choices: RefCell<HashMap<Identifier, Choice>>,
     variables: HashMap<String, String>,
     cache: Box<dyn VarsCache>,
+    suggestions: Option<Box<dyn VarsDefaultValues>>,
+    non_interactive: bool,
+    secrets: HashSet<Identifier>,
+    preview_args: Vec<String>,
 }
 
 impl UserInterface {
@@ -35,6 +41,10 @@ impl UserInterface {
             choices: RefCell::new(HashMap::new()),
             variables,
             cache,
+            suggestions: None,
+            non_interactive: false,
+            secrets: HashSet::new(),
+            preview_args: vec![],
         })
     }
     pub fn with_identifier(
@@ -47,9 +57,52 @@ impl UserInterface {
             choices: RefCell::new(HashMap::new()),
             variables,
             cache,
+            suggestions: None,
+            non_interactive: false,
+            secrets: HashSet::new(),
+            preview_args: vec![],
         })
     }
 
+    /// the provided values are offered as the pre-selected choice of their var,
+    /// the user can still pick another one.
+    pub fn with_suggestions(mut self, suggestions: Box<dyn VarsDefaultValues>) -> Self {
+        self.suggestions = Some(suggestions);
+        self
+    }
+
+    /// the user is never prompted, vars are resolved from their `SAM_<NS>_<VAR>` environment
+    /// variable or when they only have one choice.
+    pub fn with_non_interactive(mut self, non_interactive: bool) -> Self {
+        self.non_interactive = non_interactive;
+        self
+    }
+
+    /// global options passed to the sam process rendering the previews.
+    pub fn with_preview_args(mut self, args: Vec<String>) -> Self {
+        self.preview_args = args;
+        self
+    }
+
+    /// the choices of these vars are never cached nor passed to the preview.
+    pub fn with_secrets(mut self, secrets: impl IntoIterator<Item = Identifier>) -> Self {
+        self.secrets = secrets.into_iter().collect();
+        self
+    }
+
+    fn env_choice(&self, var: &Identifier) -> Option<Choice> {
+        if !self.non_interactive {
+            return None;
+        }
+        std::env::var(var.env_name()).ok().map(Choice::from_value)
+    }
+
+    fn suggestion(&self, var: &Identifier) -> Option<&Choice> {
+        self.suggestions
+            .as_ref()
+            .and_then(|suggestions| suggestions.default_value(var))
+    }
+
     fn skim_options<'ui>(
         prompt: &'ui str,
         preview_command: &'ui str,
@@ -67,11 +120,23 @@ impl UserInterface {
     }
 
     pub fn choose(&self, choices: Vec<UISelector>, prompt: &str) -> Result<usize, ErrorsUI> {
+        let preview_command = self.preview_command();
+        self.choose_with_preview(choices, prompt, &preview_command)
+    }
+
+    fn choose_with_preview(
+        &self,
+        choices: Vec<UISelector>,
+        prompt: &str,
+        preview_command: &str,
+    ) -> Result<usize, ErrorsUI> {
+        if self.non_interactive {
+            return Err(ErrorsUI::NonInteractive);
+        }
         let (s, r) = bounded(choices.len());
         let source = choices.clone();
         iterator_into_sender(source.into_iter(), s)?;
-        let preview_command = self.preview_command();
-        let options = UserInterface::skim_options(prompt, &preview_command)?;
+        let options = UserInterface::skim_options(prompt, preview_command)?;
         let output = Skim::run_with(&options, Some(r)).ok_or(ErrorsUI::SkimNoSelection)?;
 
         if output.is_abort {
@@ -94,15 +159,81 @@ impl UserInterface {
         }
     }
 
+    /// lets the user pick one of the provided actions, returns None when the selection is aborted.
+    pub fn select_action<'a>(
+        &self,
+        actions: &[&'a str],
+        prompt: &str,
+    ) -> Result<Option<&'a str>, ErrorsUI> {
+        let items: Vec<UISelector> = actions.iter().map(|a| TextItem::from_text(*a)).collect();
+        match self.choose_with_preview(items, prompt, "") {
+            Ok(idx) => Ok(actions.get(idx).copied()),
+            Err(ErrorsUI::SkimAborted) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
     fn preview_command(&'_ self) -> String {
         let borrowed_choices = self.choices.borrow();
-        let preview = PreviewSkim::new(&borrowed_choices);
+        let preview = PreviewSkim::new(&borrowed_choices).with_args(&self.preview_args);
         if let Some(alias) = self.selected_identifier.borrow().deref() {
             preview.preview_for_identifier(alias)
         } else {
             preview.preview()
         }
     }
+
+    // the output of commands is cached unless `use_cache` is false or the var is secret.
+    fn resolve_dynamic_with(
+        &self,
+        var: Identifier,
+        sh_cmd: ShellCommand<String>,
+        use_cache: bool,
+    ) -> Result<Choice, ErrorsResolver> {
+        let use_cache = use_cache && !self.secrets.contains(&var);
+        if let Some(choice) = self.env_choice(&var) {
+            return Ok(choice);
+        }
+        let cmd_key = sh_cmd
+            .replace_env_vars_in_command(&self.variables)
+            .map_err(|e| ErrorsResolver::DynamicResolveFailure(var.clone(), Box::new(e)))?;
+
+        let cache_entry = if use_cache {
+            self.cache.get(cmd_key.value())
+        } else {
+            Ok(None)
+        };
+        let (stdout_output, stderr) = if let Ok(Some(out)) = cache_entry {
+            (out.as_bytes().to_owned(), vec![])
+        } else {
+            let mut to_run = ShellCommand::make_command(sh_cmd.clone());
+            to_run.envs(&self.variables);
+            let output = to_run
+                .output()
+                .map_err(|e| ErrorsResolver::DynamicResolveFailure(var.clone(), e.into()))?;
+            if use_cache && output.status.code() == Some(0) && output.stderr.is_empty() {
+                self.cache
+                    .put(
+                        &var,
+                        cmd_key.value(),
+                        &String::from_utf8_lossy(output.stdout.as_slice()).to_owned(),
+                    )
+                    .map_err(|e| ErrorsResolver::DynamicResolveFailure(var.clone(), Box::new(e)))?;
+            }
+            (output.stdout, output.stderr)
+        };
+
+        let choices = read_choices(stdout_output.as_slice());
+        match choices {
+            Err(e) => Err(ErrorsResolver::DynamicResolveFailure(var, e.into())),
+            Ok(v) if !v.is_empty() => self.resolve_static(var, v.into_iter()),
+            Ok(_) => Err(ErrorsResolver::DynamicResolveEmpty(
+                var,
+                sh_cmd.value().to_owned(),
+                std::str::from_utf8(&stderr).unwrap_or("").to_owned(),
+            )),
+        }
+    }
 }
 #[derive(Debug, Error)]
 pub enum ErrorsUI {
@@ -114,6 +245,8 @@ pub enum ErrorsUI {
     SkimNoSelection,
     #[error("the program was aborted")]
     SkimAborted,
+    #[error("sam runs non interactively, it can't prompt for a selection")]
+    NonInteractive,
     #[error("an unexpected error happend while filling the preview window {0}")]
     IOError(#[from] std::io::Error),
     #[error("an unexpected error happend while initialising the preview window {0}")]
@@ -196,6 +329,22 @@ impl Into<Command> for AliasItem {
     }
 }
 
+struct TextItem {
+    inner: String,
+}
+
+impl TextItem {
+    fn from_text(text: impl Into<String>) -> UISelector {
+        Arc::new(TextItem { inner: text.into() })
+    }
+}
+
+impl SkimItem for TextItem {
+    fn text(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self.inner.as_str())
+    }
+}
+
 struct ChoiceItem {
     inner: Choice,
 }
@@ -218,13 +367,31 @@ impl SkimItem for ChoiceItem {
 
 impl Resolver for UserInterface {
     fn resolve_input(&self, var: Identifier, prompt: &str) -> Result<Choice, ErrorsResolver> {
+        if let Some(choice) = self.env_choice(&var) {
+            return Ok(choice);
+        }
+        if self.non_interactive {
+            return Err(ErrorsResolver::InteractionRequired(var));
+        }
         let mut buffer = String::new();
-        println!(
-            "Please provide an input for variable {}.\n{} :",
-            &var, prompt
-        );
+        let suggestion = self.suggestion(&var);
+        match suggestion {
+            Some(choice) => eprintln!(
+                "Please provide an input for variable {}, leave it empty to use '{}'.\n{} :",
+                &var,
+                choice.value(),
+                prompt
+            ),
+            None => eprintln!(
+                "Please provide an input for variable {}.\n{} :",
+                &var, prompt
+            ),
+        }
         match std::io::stdin().read_line(&mut buffer) {
-            Ok(_) => Ok(Choice::new(buffer.replace("\n", ""), None)),
+            Ok(_) => match (buffer.replace("\n", ""), suggestion) {
+                (input, Some(choice)) if input.is_empty() => Ok(choice.clone()),
+                (input, _) => Ok(Choice::new(input, None)),
+            },
             Err(err) => Err(ErrorsResolver::NoInputWasProvided(var, err.to_string())),
         }
     }
@@ -232,41 +399,18 @@ impl Resolver for UserInterface {
     where
         CMD: Into<ShellCommand<String>>,
     {
-        let sh_cmd = cmd.into();
-        let cmd_key = sh_cmd
-            .replace_env_vars_in_command(&self.variables)
-            .map_err(|e| ErrorsResolver::DynamicResolveFailure(var.clone(), Box::new(e)))?;
-
-        let cache_entry = self.cache.get(cmd_key.value());
-        let (stdout_output, stderr) = if let Ok(Some(out)) = cache_entry {
-            (out.as_bytes().to_owned(), vec![])
-        } else {
-            let mut to_run = ShellCommand::make_command(sh_cmd.clone());
-            to_run.envs(&self.variables);
-            let output = to_run
-                .output()
-                .map_err(|e| ErrorsResolver::DynamicResolveFailure(var.clone(), e.into()))?;
-            if output.status.code() == Some(0) && output.stderr.is_empty() {
-                self.cache
-                    .put(
-                        cmd_key.value(),
-                        &String::from_utf8_lossy(output.stdout.as_slice()).to_owned(),
-                    )
-                    .map_err(|e| ErrorsResolver::DynamicResolveFailure(var.clone(), Box::new(e)))?;
-            }
-            (output.stdout, output.stderr)
-        };
+        self.resolve_dynamic_with(var, cmd.into(), true)
+    }
 
-        let choices = read_choices(stdout_output.as_slice());
-        match choices {
-            Err(e) => Err(ErrorsResolver::DynamicResolveFailure(var, e.into())),
-            Ok(v) if !v.is_empty() => self.resolve_static(var, v.into_iter()),
-            Ok(_) => Err(ErrorsResolver::DynamicResolveEmpty(
-                var,
-                sh_cmd.value().to_owned(),
-                std::str::from_utf8(&stderr).unwrap_or("").to_owned(),
-            )),
-        }
+    fn resolve_dynamic_uncached<CMD>(
+        &self,
+        var: Identifier,
+        cmd: CMD,
+    ) -> Result<Choice, ErrorsResolver>
+    where
+        CMD: Into<ShellCommand<String>>,
+    {
+        self.resolve_dynamic_with(var, cmd.into(), false)
     }
 
     fn resolve_static(
@@ -274,6 +418,9 @@ impl Resolver for UserInterface {
         var: Identifier,
         cmd: impl Iterator<Item = Choice>,
     ) -> Result<Choice, ErrorsResolver> {
+        if let Some(choice) = self.env_choice(&var) {
+            return Ok(choice);
+        }
         let mut choices: Vec<Choice> = cmd.collect();
         if choices.is_empty() {
             return Err(ErrorsResolver::NoChoiceWasAvailable(var));
@@ -281,6 +428,16 @@ impl Resolver for UserInterface {
         if choices.len() == 1 {
             return Ok(choices.pop().unwrap());
         }
+        if self.non_interactive {
+            return Err(ErrorsResolver::InteractionRequired(var));
+        }
+        // skim places the cursor on the first item, that's how the suggestion gets pre-selected.
+        if let Some(suggested) = self.suggestion(&var) {
+            if let Some(position) = choices.iter().position(|c| c.value() == suggested.value()) {
+                let choice = choices.remove(position);
+                choices.insert(0, choice);
+            }
+        }
         let items: Vec<UISelector> = choices
             .clone()
             .into_iter()
@@ -296,8 +453,11 @@ impl Resolver for UserInterface {
                     .map(|e| e.to_owned())
                     .ok_or_else(|| ErrorsResolver::NoChoiceWasSelected(var.clone()))
             })?;
-        let mut mp = self.choices.borrow_mut();
-        (*mp).insert(var, choice.clone());
+        // the choices end up on the command line of the preview.
+        if !self.secrets.contains(&var) {
+            let mut mp = self.choices.borrow_mut();
+            (*mp).insert(var, choice.clone());
+        }
         Ok(choice)
     }
 
@@ -333,6 +493,55 @@ impl Resolver for UserInterface {
     }
 }
 
+impl EntrySelector<CacheEntry> for UserInterface {
+    fn select_entry(
+        &self,
+        data: impl Iterator<Item = (String, CacheEntry)>,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let mut keys: Vec<String> = data.map(|(key, _)| key).collect();
+        if keys.is_empty() {
+            return Ok(None);
+        }
+        keys.sort();
+        let items: Vec<UISelector> = keys.iter().map(TextItem::from_text).collect();
+        let borrowed_choices = self.choices.borrow();
+        let preview_command = PreviewSkim::new(&borrowed_choices)
+            .with_args(&self.preview_args)
+            .preview_cache_entry();
+        match self.choose_with_preview(items, "Choose a cache entry to delete > ", &preview_command)
+        {
+            Ok(idx) => Ok(keys.get(idx).cloned()),
+            Err(ErrorsUI::SkimAborted) => Ok(None),
+            Err(err) => Err(Box::new(err)),
+        }
+    }
+}
+
+impl SequentialEntrySelector<HistoryEntry> for UserInterface {
+    fn select_entry(
+        &self,
+        data: impl Iterator<Item = (usize, HistoryEntry)>,
+    ) -> Result<Option<usize>, Box<dyn std::error::Error>> {
+        // most recent entries first, the position keeps identical entries apart.
+        let mut entries: Vec<(usize, HistoryEntry)> = data.collect();
+        if entries.is_empty() {
+            return Ok(None);
+        }
+        entries.reverse();
+        let items: Vec<UISelector> = entries
+            .iter()
+            .map(|(position, entry)| {
+                TextItem::from_text(format!("{}\t{}", position, entry.summary()))
+            })
+            .collect();
+        match self.choose_with_preview(items, "Choose a history entry > ", "echo {}") {
+            Ok(idx) => Ok(entries.get(idx).map(|(position, _)| *position)),
+            Err(ErrorsUI::SkimAborted) => Ok(None),
+            Err(err) => Err(Box::new(err)),
+        }
+    }
+}
+
 fn iterator_into_sender<I, U>(it: I, s: Sender<U>) -> Result<(), ErrorsUI>
 where
     U: Clone,
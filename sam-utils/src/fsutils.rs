@@ -1,3 +1,5 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use rand::Rng;
 use std::cell::RefCell;
 use std::env::temp_dir;
@@ -51,31 +53,138 @@ impl TempFile {
     }
 }
 
+pub const IGNORE_FILE_NAME: &str = ".samignore";
+
+/// WalkFilter restricts the files returned by `walk_dir_with` using globs
+/// matched against the path relative to the walked directory.
+#[derive(Debug, Clone, Default)]
+pub struct WalkFilter {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+impl WalkFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self> {
+        Ok(WalkFilter {
+            include: Self::glob_set(include)?,
+            exclude: Self::glob_set(exclude)?,
+        })
+    }
+
+    fn glob_set(patterns: &[String]) -> Result<Option<GlobSet>> {
+        if patterns.is_empty() {
+            return Ok(None);
+        }
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            builder.add(Glob::new(pattern)?);
+        }
+        Ok(Some(builder.build()?))
+    }
+
+    fn excludes(&self, relative: &Path) -> bool {
+        self.exclude
+            .as_ref()
+            .map(|set| set.is_match(relative))
+            .unwrap_or(false)
+    }
+
+    fn includes(&self, relative: &Path) -> bool {
+        self.include
+            .as_ref()
+            .map(|set| set.is_match(relative))
+            .unwrap_or(true)
+    }
+}
+
 pub fn walk_dir(path: &Path) -> Result<Vec<PathBuf>> {
+    walk_dir_with(path, &WalkFilter::default())
+}
+
+/// lists the files of `path` and the entries of its direct sub-directories,
+/// leaving out whatever its `.samignore` or the filter's `exclude` globs match.
+/// When the filter has `include` globs, only the files they match are kept.
+pub fn walk_dir_with(path: &Path, filter: &WalkFilter) -> Result<Vec<PathBuf>> {
+    let ignore_file = path.join(IGNORE_FILE_NAME);
+    let ignored = if ignore_file.is_file() {
+        let mut builder = GitignoreBuilder::new(path);
+        if let Some(err) = builder.add(&ignore_file) {
+            return Err(err.into());
+        }
+        builder.build()?
+    } else {
+        Gitignore::empty()
+    };
+    let skipped = |content: &Path| {
+        let relative = content.strip_prefix(path).unwrap_or(content);
+        ignored
+            .matched_path_or_any_parents(content, content.is_dir())
+            .is_ignore()
+            || filter.excludes(relative)
+    };
+    let kept = |content: &Path| {
+        let relative = content.strip_prefix(path).unwrap_or(content);
+        !skipped(content) && (content.is_dir() || filter.includes(relative))
+    };
+
     let dir_content = std::fs::read_dir(path)?;
     let paths = dir_content.flat_map(|e| e.map(|e| e.path()));
     let mut deque = vec![];
     for content in paths {
+        if content == ignore_file || skipped(&content) {
+            continue;
+        }
         if content.is_dir() {
             let cur_dir = std::fs::read_dir(content.as_path())?;
             let paths = cur_dir.flat_map(|e| e.map(|e| e.path()));
-            deque.extend(paths);
+            deque.extend(paths.filter(|p| kept(p)));
         }
-        if content.is_file() {
+        if content.is_file() && kept(&content) {
             deque.push(content);
         }
     }
     Ok(deque)
 }
 
+/// expands a leading `~`, `$HOME` and any other `$VAR` or `${VAR}` environment variable.
+/// Variables that are not set are left untouched.
 pub fn replace_home_variable(path: String) -> String {
     let home_dir_o = dirs::home_dir().and_then(|e| e.into_os_string().into_string().ok());
-    if let Some(home_dir) = home_dir_o {
-        if path.contains("$HOME") {
-            return path.replace("$HOME", &home_dir);
+    let path = match home_dir_o {
+        Some(home_dir) if path == "~" || path.starts_with("~/") => {
+            format!("{}{}", home_dir, &path[1..])
         }
+        Some(home_dir) => path.replace("$HOME", &home_dir),
+        None => path,
+    };
+    expand_env_variables(&path)
+}
+
+fn expand_env_variables(path: &str) -> String {
+    let mut expanded = String::with_capacity(path.len());
+    let mut rest = path;
+    while let Some(position) = rest.find('$') {
+        expanded.push_str(&rest[..position]);
+        let after = &rest[position + 1..];
+        let (name, consumed) = if let Some(braced) = after.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            }
+        } else {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            (&after[..end], end)
+        };
+        match std::env::var(name) {
+            Ok(value) if !name.is_empty() => expanded.push_str(&value),
+            _ => expanded.push_str(&rest[position..position + 1 + consumed]),
+        }
+        rest = &after[consumed..];
     }
-    path
+    expanded.push_str(rest);
+    expanded
 }
 
 pub fn ensure_exists(path: PathBuf) -> Result<PathBuf> {
@@ -120,4 +229,70 @@ pub enum ErrorsFS {
     PathInsufficientPermission(PathBuf),
     #[error("got an unexpected error {0}")]
     UnexpectedIOError(#[from] std::io::Error),
+    #[error("could not read the ignore file\n-> {0}")]
+    InvalidIgnoreFile(#[from] ignore::Error),
+    #[error("invalid glob pattern\n-> {0}")]
+    InvalidGlob(#[from] globset::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        replace_home_variable, walk_dir, walk_dir_with, TempDirectory, WalkFilter, IGNORE_FILE_NAME,
+    };
+    use std::fs;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_walk_dir_with_filters() {
+        let root = TempDirectory::new().expect("could not create a temporary directory");
+        for dir in ["docker", "k8s", "drafts"] {
+            fs::create_dir(root.path.join(dir)).unwrap();
+            fs::write(root.path.join(dir).join("aliases.yaml"), "").unwrap();
+            fs::write(root.path.join(dir).join("vars.yaml"), "").unwrap();
+        }
+        fs::write(root.path.join(IGNORE_FILE_NAME), "drafts/\n*/vars.yaml\n").unwrap();
+
+        let sorted = |mut paths: Vec<PathBuf>| {
+            paths.sort();
+            paths
+        };
+        let files = sorted(walk_dir(&root.path).expect("could not walk the directory"));
+        assert_eq!(
+            files,
+            vec![
+                root.path.join("docker").join("aliases.yaml"),
+                root.path.join("k8s").join("aliases.yaml"),
+            ]
+        );
+
+        let filter = WalkFilter::new(&[String::from("*/aliases.*")], &[String::from("k8s/**")])
+            .expect("globs should be valid");
+        let files = sorted(walk_dir_with(&root.path, &filter).expect("could not walk"));
+        assert_eq!(files, vec![root.path.join("docker").join("aliases.yaml")]);
+
+        assert!(WalkFilter::new(&[String::from("a[")], &[]).is_err());
+    }
+
+    #[test]
+    fn test_replace_home_variable() {
+        let home = dirs::home_dir().unwrap().to_string_lossy().to_string();
+        std::env::set_var("SAM_TEST_ROOT", "/srv/sam");
+        assert_eq!(
+            replace_home_variable(String::from("~/sam")),
+            format!("{}/sam", home)
+        );
+        assert_eq!(
+            replace_home_variable(String::from("$HOME/sam")),
+            format!("{}/sam", home)
+        );
+        assert_eq!(
+            replace_home_variable(String::from("${SAM_TEST_ROOT}/a/$SAM_TEST_ROOT")),
+            "/srv/sam/a//srv/sam"
+        );
+        assert_eq!(
+            replace_home_variable(String::from("/a/$SAM_TEST_UNSET/~/${b")),
+            "/a/$SAM_TEST_UNSET/~/${b"
+        );
+    }
 }